@@ -9,5 +9,6 @@ cp -r ./target/dx/website/release/web/public/* docs
 cp docs/assets/favicon.ico docs/favicon.ico
 mv docs/assets/CNAME docs/CNAME
 mv docs/assets/robots.txt docs/robots.txt
-mv docs/assets/sitemap.txt docs/sitemap.txt
+mv docs/assets/feed.xml docs/feed.xml
+mv docs/assets/atom.xml docs/atom.xml
 rm -fr ./static
\ No newline at end of file
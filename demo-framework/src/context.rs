@@ -0,0 +1,52 @@
+use crate::globals::{Globals, LAYOUT_ENTRIES};
+use crate::GlobalUniforms;
+
+/// The GPU a scene renders with, and the uniforms shared by all scenes.
+pub struct Context {
+    pub device: wgpu::Device,
+    pub queue: wgpu::Queue,
+    /// The format of the textures scenes render to.
+    pub format: wgpu::TextureFormat,
+    globals: Globals,
+}
+
+impl Context {
+    pub fn new(device: wgpu::Device, queue: wgpu::Queue, format: wgpu::TextureFormat) -> Self {
+        let globals = Globals::new(&device);
+        Self {
+            device,
+            queue,
+            format,
+            globals,
+        }
+    }
+
+    /// The values of the global uniforms for the current frame.
+    pub fn uniforms(&self) -> &GlobalUniforms {
+        &self.globals.uniforms
+    }
+
+    /// Updates the global uniforms, which scenes see from their next
+    /// [`crate::Scene::update`].
+    pub fn set_uniforms(&mut self, uniforms: GlobalUniforms) {
+        self.globals.uniforms = uniforms;
+        self.queue
+            .write_buffer(&self.globals.buffer, 0, bytemuck::bytes_of(&uniforms));
+    }
+
+    /// The layout of [`Context::globals_bind_group`], for the pipeline layouts
+    /// of scenes using [`crate::GLOBALS_WGSL`].
+    pub fn globals_layout(&self) -> &wgpu::BindGroupLayout {
+        &self.globals.layout
+    }
+
+    /// The entries of [`Context::globals_layout`], for the
+    /// [`crate::PipelineInterface`] of shaders using it.
+    pub fn globals_layout_entries(&self) -> &'static [wgpu::BindGroupLayoutEntry] {
+        &LAYOUT_ENTRIES
+    }
+
+    pub fn globals_bind_group(&self) -> &wgpu::BindGroup {
+        &self.globals.bind_group
+    }
+}
@@ -0,0 +1,78 @@
+/// The uniforms every scene can read, updated before each frame.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct GlobalUniforms {
+    /// The size of the render target in pixels.
+    pub resolution: [f32; 2],
+    /// The last position of the pointer over the canvas, in pixels from its
+    /// top left corner.
+    pub mouse: [f32; 2],
+    /// Seconds the scene has been rendering for, not counting pauses.
+    pub time: f32,
+    /// Seconds since the previous frame.
+    pub delta_time: f32,
+}
+
+/// WGSL declaring [`GlobalUniforms`] as `globals`, to put before the shaders
+/// of scenes using them with [`crate::Context::globals_layout`] as group 0.
+pub const GLOBALS_WGSL: &str = "struct Globals {
+    resolution: vec2<f32>,
+    mouse: vec2<f32>,
+    time: f32,
+    delta_time: f32,
+}
+
+@group(0) @binding(0)
+var<uniform> globals: Globals;
+";
+
+/// The entries of [`crate::Context::globals_layout`]. The minimum size lets
+/// pipelines reject shaders expecting more than [`GlobalUniforms`].
+pub(crate) const LAYOUT_ENTRIES: [wgpu::BindGroupLayoutEntry; 1] = [wgpu::BindGroupLayoutEntry {
+    binding: 0,
+    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+    ty: wgpu::BindingType::Buffer {
+        ty: wgpu::BufferBindingType::Uniform,
+        has_dynamic_offset: false,
+        min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<GlobalUniforms>() as u64),
+    },
+    count: None,
+}];
+
+/// The buffer holding the [`GlobalUniforms`] and its bind group.
+pub(crate) struct Globals {
+    pub(crate) uniforms: GlobalUniforms,
+    pub(crate) buffer: wgpu::Buffer,
+    pub(crate) layout: wgpu::BindGroupLayout,
+    pub(crate) bind_group: wgpu::BindGroup,
+}
+
+impl Globals {
+    pub(crate) fn new(device: &wgpu::Device) -> Self {
+        let uniforms = GlobalUniforms::default();
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("globals"),
+            size: std::mem::size_of::<GlobalUniforms>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("globals"),
+            entries: &LAYOUT_ENTRIES,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("globals"),
+            layout: &layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        });
+        Self {
+            uniforms,
+            buffer,
+            layout,
+            bind_group,
+        }
+    }
+}
@@ -0,0 +1,336 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use wasm_bindgen::prelude::*;
+use web_sys::js_sys;
+
+use crate::{FrameStats, ShaderDiagnostic};
+
+/// The longest side of the render target, in pixels, unless the page sets
+/// another cap with [`DemoHandle::set_max_resolution`].
+pub const DEFAULT_MAX_RESOLUTION: u32 = 2048;
+
+/// A shader waiting for the render loop, with where to send how it went.
+pub(crate) type ShaderChange = (String, oneshot::Sender<Result<(), Vec<ShaderDiagnostic>>>);
+
+/// State shared between the render loop and whoever controls it.
+#[derive(Default)]
+pub(crate) struct Controls {
+    paused: Cell<bool>,
+    offscreen: Cell<bool>,
+    hidden: Cell<bool>,
+    stopped: Cell<bool>,
+    size: Cell<Option<(u32, u32)>>,
+    canvas_size: Cell<(u32, u32)>,
+    max_resolution: Cell<Option<u32>>,
+    mouse: Cell<[f32; 2]>,
+    stats: Cell<FrameStats>,
+    shader: RefCell<Option<ShaderChange>>,
+    wake: Cell<Option<oneshot::Sender<()>>>,
+}
+
+impl Controls {
+    pub(crate) fn is_stopped(&self) -> bool {
+        self.stopped.get()
+    }
+
+    pub(crate) fn should_render(&self) -> bool {
+        !self.paused.get() && !self.offscreen.get() && !self.hidden.get()
+    }
+
+    /// The size to render at: the one set with [`DemoHandle::resize`], or else
+    /// the size of the canvas in device pixels, scaled down so neither side
+    /// is longer than the max resolution or `max_texture_size`.
+    pub(crate) fn size(&self, max_texture_size: u32) -> (u32, u32) {
+        if let Some(size) = self.size.get() {
+            return size;
+        }
+        let (width, height) = self.canvas_size.get();
+        let max = self
+            .max_resolution
+            .get()
+            .unwrap_or(DEFAULT_MAX_RESOLUTION)
+            .min(max_texture_size);
+        let scale = (max as f64 / width.max(height) as f64).min(1.0);
+        // A canvas that isn't laid out has no size, which can't be configured
+        (
+            ((width as f64 * scale).round() as u32).max(1),
+            ((height as f64 * scale).round() as u32).max(1),
+        )
+    }
+
+    /// See [`crate::GlobalUniforms::mouse`].
+    pub(crate) fn mouse(&self) -> [f32; 2] {
+        self.mouse.get()
+    }
+
+    pub(crate) fn set_stats(&self, stats: FrameStats) {
+        self.stats.set(stats);
+    }
+
+    /// The shader given to [`DemoHandle::set_shader`] since the last call.
+    pub(crate) fn take_shader(&self) -> Option<ShaderChange> {
+        self.shader.take()
+    }
+
+    /// Lets a sleeping render loop check its state again.
+    fn wake(&self) {
+        if let Some(wake) = self.wake.take() {
+            let _ = wake.send(());
+        }
+    }
+
+    pub(crate) async fn wait_until_woken(&self) {
+        let (wake, woken) = oneshot::channel();
+        self.wake.set(Some(wake));
+        let _ = woken.await;
+    }
+}
+
+/// How the page refers to the graphics API behind `backend`.
+pub(crate) fn backend_name(backend: wgpu::Backend) -> &'static str {
+    match backend {
+        wgpu::Backend::BrowserWebGpu => "WebGPU",
+        wgpu::Backend::Gl => "WebGL2",
+        other => other.to_str(),
+    }
+}
+
+/// Controls a running demo from JS or Rust.
+#[wasm_bindgen]
+pub struct DemoHandle {
+    controls: Rc<Controls>,
+    backend: wgpu::Backend,
+}
+
+impl DemoHandle {
+    pub(crate) fn new(controls: Rc<Controls>, backend: wgpu::Backend) -> Self {
+        Self { controls, backend }
+    }
+}
+
+#[wasm_bindgen]
+impl DemoHandle {
+    pub fn pause(&self) {
+        self.controls.paused.set(true);
+    }
+
+    pub fn resume(&self) {
+        self.controls.paused.set(false);
+        self.controls.wake();
+    }
+
+    /// Ends the render loop for good, releasing the GPU resources.
+    pub fn stop(&self) {
+        self.controls.stopped.set(true);
+        self.controls.wake();
+    }
+
+    /// Renders at `width`x`height` instead of following the size of the
+    /// canvas, or follows it again when either is `None`.
+    pub fn resize(&self, width: Option<u32>, height: Option<u32>) {
+        let size = width
+            .zip(height)
+            .map(|(width, height)| (width.max(1), height.max(1)));
+        self.controls.size.set(size);
+        self.controls.wake();
+    }
+
+    /// Caps the longest side of the render target to `max` pixels, or to
+    /// [`DEFAULT_MAX_RESOLUTION`] when `None`. Large canvases on high density
+    /// screens otherwise cost a lot of fill rate.
+    pub fn set_max_resolution(&self, max: Option<u32>) {
+        self.controls.max_resolution.set(max.map(|max| max.max(1)));
+        self.controls.wake();
+    }
+
+    /// Replaces the shader of the scene, if it supports it. Resolves to the
+    /// [`ShaderDiagnostic`]s of `source` once the render loop tried it, none
+    /// meaning the shader is running.
+    pub fn set_shader(&self, source: String) -> js_sys::Promise {
+        let (reply, result) = oneshot::channel();
+        self.controls.shader.replace(Some((source, reply)));
+        self.controls.wake();
+        wasm_bindgen_futures::future_to_promise(async move {
+            let diagnostics = match result.await {
+                Ok(Ok(())) => vec![],
+                Ok(Err(diagnostics)) => diagnostics,
+                Err(_) => {
+                    return Err("the demo stopped, or another shader replaced this one".into())
+                }
+            };
+            Ok(diagnostics
+                .into_iter()
+                .map(JsValue::from)
+                .collect::<js_sys::Array>()
+                .into())
+        })
+    }
+
+    /// The graphics API the demo renders with, `"WebGPU"` or `"WebGL2"`.
+    #[wasm_bindgen(getter)]
+    pub fn backend(&self) -> String {
+        backend_name(self.backend).to_string()
+    }
+
+    /// How fast the demo renders, as of the last frame.
+    #[wasm_bindgen(getter)]
+    pub fn stats(&self) -> FrameStats {
+        self.controls.stats.get()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn running(&self) -> bool {
+        !self.controls.is_stopped() && self.controls.should_render()
+    }
+}
+
+/// Whether the browser can report sizes in device pixels, which Safari can't
+/// and throws for.
+fn supports_device_pixel_content_box() -> bool {
+    js_sys::Reflect::get(&js_sys::global(), &"ResizeObserverEntry".into())
+        .and_then(|entry| js_sys::Reflect::get(&entry, &"prototype".into()))
+        .and_then(|prototype| js_sys::Reflect::has(&prototype, &"devicePixelContentBoxSize".into()))
+        .unwrap_or(false)
+}
+
+/// A size in CSS pixels in device pixels.
+fn device_pixel_size(width: f64, height: f64) -> (u32, u32) {
+    let ratio = web_sys::window().map_or(1.0, |window| window.device_pixel_ratio());
+    (
+        (width * ratio).round() as u32,
+        (height * ratio).round() as u32,
+    )
+}
+
+/// Pauses the loop while the canvas is off-screen or the tab is hidden,
+/// follows the size of the canvas and the pointer over it, for as long as it's
+/// alive.
+pub(crate) struct Watchers {
+    observer: web_sys::IntersectionObserver,
+    _on_intersection: Closure<dyn FnMut(js_sys::Array)>,
+    resize_observer: web_sys::ResizeObserver,
+    _on_resize: Closure<dyn FnMut(js_sys::Array)>,
+    document: web_sys::Document,
+    on_visibility_change: Closure<dyn FnMut()>,
+    canvas: web_sys::HtmlCanvasElement,
+    on_pointer_move: Closure<dyn FnMut(web_sys::MouseEvent)>,
+}
+
+impl Watchers {
+    pub(crate) fn new(
+        canvas: &web_sys::HtmlCanvasElement,
+        document: web_sys::Document,
+        controls: &Rc<Controls>,
+    ) -> Result<Self, JsValue> {
+        let on_intersection = Closure::<dyn FnMut(js_sys::Array)>::new({
+            let controls = controls.clone();
+            move |entries: js_sys::Array| {
+                if let Some(entry) = entries.iter().last() {
+                    let entry: web_sys::IntersectionObserverEntry = entry.unchecked_into();
+                    controls.offscreen.set(!entry.is_intersecting());
+                }
+                // Also lets the loop notice the canvas was removed from the page
+                controls.wake();
+            }
+        });
+        let observer =
+            web_sys::IntersectionObserver::new(on_intersection.as_ref().unchecked_ref())?;
+        observer.observe(canvas);
+
+        // Until the first observation, which may come after the first frame
+        controls.canvas_size.set(device_pixel_size(
+            canvas.client_width() as f64,
+            canvas.client_height() as f64,
+        ));
+        let device_pixels = supports_device_pixel_content_box();
+        let on_resize = Closure::<dyn FnMut(js_sys::Array)>::new({
+            let controls = controls.clone();
+            move |entries: js_sys::Array| {
+                let Some(entry) = entries.iter().last() else {
+                    return;
+                };
+                let entry: web_sys::ResizeObserverEntry = entry.unchecked_into();
+                let size = if device_pixels {
+                    // Exact, and also observed when the pixel ratio changes
+                    let size: web_sys::ResizeObserverSize = entry
+                        .device_pixel_content_box_size()
+                        .get(0)
+                        .unchecked_into();
+                    (size.inline_size() as u32, size.block_size() as u32)
+                } else {
+                    let rect = entry.content_rect();
+                    device_pixel_size(rect.width(), rect.height())
+                };
+                controls.canvas_size.set(size);
+                controls.wake();
+            }
+        });
+        let resize_observer = web_sys::ResizeObserver::new(on_resize.as_ref().unchecked_ref())?;
+        if device_pixels {
+            let options = web_sys::ResizeObserverOptions::new();
+            options.set_box(web_sys::ResizeObserverBoxOptions::DevicePixelContentBox);
+            resize_observer.observe_with_options(canvas, &options);
+        } else {
+            resize_observer.observe(canvas);
+        }
+
+        let on_visibility_change = Closure::<dyn FnMut()>::new({
+            let controls = controls.clone();
+            let document = document.clone();
+            move || {
+                controls.hidden.set(document.hidden());
+                controls.wake();
+            }
+        });
+        document.add_event_listener_with_callback(
+            "visibilitychange",
+            on_visibility_change.as_ref().unchecked_ref(),
+        )?;
+        controls.hidden.set(document.hidden());
+
+        let on_pointer_move = Closure::<dyn FnMut(web_sys::MouseEvent)>::new({
+            let controls = controls.clone();
+            let canvas = canvas.clone();
+            move |event: web_sys::MouseEvent| {
+                // From CSS pixels to the pixels of the render target
+                let scale_x = canvas.width() as f32 / canvas.client_width().max(1) as f32;
+                let scale_y = canvas.height() as f32 / canvas.client_height().max(1) as f32;
+                controls.mouse.set([
+                    event.offset_x() as f32 * scale_x,
+                    event.offset_y() as f32 * scale_y,
+                ]);
+            }
+        });
+        canvas.add_event_listener_with_callback(
+            "pointermove",
+            on_pointer_move.as_ref().unchecked_ref(),
+        )?;
+
+        Ok(Self {
+            observer,
+            _on_intersection: on_intersection,
+            resize_observer,
+            _on_resize: on_resize,
+            document,
+            on_visibility_change,
+            canvas: canvas.clone(),
+            on_pointer_move,
+        })
+    }
+}
+
+impl Drop for Watchers {
+    fn drop(&mut self) {
+        self.observer.disconnect();
+        self.resize_observer.disconnect();
+        let _ = self.document.remove_event_listener_with_callback(
+            "visibilitychange",
+            self.on_visibility_change.as_ref().unchecked_ref(),
+        );
+        let _ = self.canvas.remove_event_listener_with_callback(
+            "pointermove",
+            self.on_pointer_move.as_ref().unchecked_ref(),
+        );
+    }
+}
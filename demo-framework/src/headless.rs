@@ -0,0 +1,233 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+use crate::registry::InitScene;
+use crate::{Context, GlobalUniforms, Scene};
+
+/// The format scenes render to without a canvas. Not sRGB, like the canvas of
+/// browsers, so the pixels hold what the shaders output.
+const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+
+/// A frame read back from the GPU.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    /// RGBA, 8 bits per channel, row after row from the top.
+    pub pixels: Vec<u8>,
+}
+
+/// Renders scenes to a texture instead of a canvas, for tests and tooling.
+pub struct Headless {
+    context: Context,
+    adapter_info: wgpu::AdapterInfo,
+    texture: wgpu::Texture,
+    width: u32,
+    height: u32,
+}
+
+impl Headless {
+    /// Sets up a `width`x`height` render target, preferring a software
+    /// adapter (such as lavapipe, llvmpipe or WARP) so the output doesn't
+    /// depend on the GPU of the machine. `WGPU_BACKEND` picks the backends
+    /// to try.
+    pub fn new(width: u32, height: u32) -> Result<Self, String> {
+        pollster::block_on(Self::new_async(width, height))
+    }
+
+    async fn new_async(width: u32, height: u32) -> Result<Self, String> {
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends: wgpu::util::backend_bits_from_env().unwrap_or(wgpu::Backends::all()),
+            ..Default::default()
+        });
+        let mut adapter = None;
+        for force_fallback_adapter in [true, false] {
+            adapter = instance
+                .request_adapter(&wgpu::RequestAdapterOptions {
+                    power_preference: wgpu::PowerPreference::LowPower,
+                    force_fallback_adapter,
+                    compatible_surface: None,
+                })
+                .await;
+            if adapter.is_some() {
+                break;
+            }
+        }
+        let adapter = adapter.ok_or("Failed to find an appropriate adapter")?;
+
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    label: None,
+                    required_features: wgpu::Features::empty(),
+                    // The same limits as in the browser, so scenes working
+                    // here work there too.
+                    required_limits: wgpu::Limits::downlevel_webgl2_defaults()
+                        .using_resolution(adapter.limits()),
+                    memory_hints: wgpu::MemoryHints::MemoryUsage,
+                },
+                None,
+            )
+            .await
+            .map_err(|err| format!("{err}"))?;
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("headless target"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+
+        Ok(Self {
+            context: Context::new(device, queue, FORMAT),
+            adapter_info: adapter.get_info(),
+            texture,
+            width,
+            height,
+        })
+    }
+
+    pub fn context(&self) -> &Context {
+        &self.context
+    }
+
+    /// The adapter rendering, to tell which one made an image.
+    pub fn adapter_info(&self) -> &wgpu::AdapterInfo {
+        &self.adapter_info
+    }
+
+    /// Creates a scene sized for the render target.
+    pub fn init(&self, init: InitScene) -> Box<dyn Scene> {
+        let mut scene = init(&self.context);
+        scene.resize(&self.context, self.width, self.height);
+        scene
+    }
+
+    /// Renders a frame of `scene` with `uniforms`, whose resolution is set to
+    /// the size of the render target, and reads it back.
+    pub fn render(
+        &mut self,
+        scene: &mut dyn Scene,
+        uniforms: GlobalUniforms,
+    ) -> Result<Image, String> {
+        self.context.set_uniforms(GlobalUniforms {
+            resolution: [self.width as f32, self.height as f32],
+            ..uniforms
+        });
+        scene.update(&self.context);
+
+        let view = self
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        let mut encoder = self
+            .context
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        scene.render(&self.context, &mut encoder, &view);
+
+        // Rows of a texture copy have to be aligned
+        let row_bytes = self.width * 4;
+        let padded_row_bytes = row_bytes.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let buffer = self.context.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("headless readback"),
+            size: (padded_row_bytes * self.height) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        encoder.copy_texture_to_buffer(
+            self.texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row_bytes),
+                    rows_per_image: None,
+                },
+            },
+            self.texture.size(),
+        );
+        self.context.queue.submit(Some(encoder.finish()));
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                let _ = sender.send(result);
+            });
+        self.context.device.poll(wgpu::Maintain::Wait);
+        receiver
+            .recv()
+            .map_err(|err| format!("{err}"))?
+            .map_err(|err| format!("{err}"))?;
+
+        let mut pixels = Vec::with_capacity((row_bytes * self.height) as usize);
+        for row in buffer
+            .slice(..)
+            .get_mapped_range()
+            .chunks(padded_row_bytes as usize)
+        {
+            pixels.extend_from_slice(&row[..row_bytes as usize]);
+        }
+        Ok(Image {
+            width: self.width,
+            height: self.height,
+            pixels,
+        })
+    }
+}
+
+impl Image {
+    pub fn read_png(path: &Path) -> Result<Self, String> {
+        let file = File::open(path).map_err(|err| format!("{}: {err}", path.display()))?;
+        let mut reader = png::Decoder::new(BufReader::new(file))
+            .read_info()
+            .map_err(|err| format!("{}: {err}", path.display()))?;
+        let info = reader.info();
+        if (info.color_type, info.bit_depth) != (png::ColorType::Rgba, png::BitDepth::Eight) {
+            return Err(format!("{}: expected an 8 bit RGBA image", path.display()));
+        }
+        let (width, height) = (info.width, info.height);
+        let mut pixels = vec![0; (width * height * 4) as usize];
+        reader
+            .next_frame(&mut pixels)
+            .map_err(|err| format!("{}: {err}", path.display()))?;
+        Ok(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    pub fn write_png(&self, path: &Path) -> Result<(), String> {
+        let file = File::create(path).map_err(|err| format!("{}: {err}", path.display()))?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(&self.pixels))
+            .map_err(|err| format!("{}: {err}", path.display()))
+    }
+
+    /// How many pixels have a channel further than `tolerance` from the same
+    /// pixel of `other`, as adapters don't rasterize edges exactly alike.
+    pub fn count_different_pixels(&self, other: &Image, tolerance: u8) -> usize {
+        if (self.width, self.height) != (other.width, other.height) {
+            return self.pixels.len().max(other.pixels.len()) / 4;
+        }
+        self.pixels
+            .chunks(4)
+            .zip(other.pixels.chunks(4))
+            .filter(|(a, b)| a.iter().zip(*b).any(|(a, b)| a.abs_diff(*b) > tolerance))
+            .count()
+    }
+}
@@ -0,0 +1,45 @@
+//! The pieces shared by the wgpu demos embedded in blog posts.
+//!
+//! A demo module implements [`Scene`] for each of its visuals, lists them in a
+//! [`Registry`] and exports a `run(canvas_id, scene)` function calling
+//! [`run`], which sets up the GPU, the global uniforms and the render loop.
+//! The loop measures itself, which the page reads with [`DemoHandle::stats`].
+//!
+//! Natively, [`Headless`] renders the same scenes to a texture instead, which
+//! the snapshot tests of demo modules compare against reference images.
+
+mod context;
+mod globals;
+mod registry;
+mod scene;
+mod shader;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod headless;
+
+#[cfg(target_arch = "wasm32")]
+mod handle;
+#[cfg(target_arch = "wasm32")]
+mod logger;
+#[cfg(target_arch = "wasm32")]
+mod stats;
+#[cfg(target_arch = "wasm32")]
+mod web;
+
+pub use context::Context;
+pub use globals::{GlobalUniforms, GLOBALS_WGSL};
+pub use naga::ShaderStage;
+pub use registry::{InitScene, Registry};
+pub use scene::Scene;
+pub use shader::{compile_wgsl, PipelineInterface, ShaderDiagnostic};
+
+#[cfg(target_arch = "wasm32")]
+pub use handle::{DemoHandle, DEFAULT_MAX_RESOLUTION};
+#[cfg(not(target_arch = "wasm32"))]
+pub use headless::{Headless, Image};
+#[cfg(target_arch = "wasm32")]
+pub use logger::init_logger;
+#[cfg(target_arch = "wasm32")]
+pub use stats::FrameStats;
+#[cfg(target_arch = "wasm32")]
+pub use web::run;
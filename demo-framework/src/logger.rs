@@ -0,0 +1,34 @@
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+/// Sends the `log` records of a demo module to the browser console, which
+/// otherwise go nowhere as the module has no logger of its own.
+struct ConsoleLogger;
+
+impl Log for ConsoleLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let message = format!("[{}] {}", record.target(), record.args()).into();
+        match record.level() {
+            Level::Error => web_sys::console::error_1(&message),
+            Level::Warn => web_sys::console::warn_1(&message),
+            Level::Info => web_sys::console::info_1(&message),
+            Level::Debug | Level::Trace => web_sys::console::debug_1(&message),
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Logs to the console from `level` up. Later calls only change the level,
+/// as there is one logger per module.
+pub fn init_logger(level: LevelFilter) {
+    static LOGGER: ConsoleLogger = ConsoleLogger;
+    let _ = log::set_logger(&LOGGER);
+    log::set_max_level(level);
+}
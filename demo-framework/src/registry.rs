@@ -0,0 +1,49 @@
+use crate::{Context, Scene};
+
+/// Creates a scene, boxed so scenes of different types can be registered.
+pub type InitScene = fn(&Context) -> Box<dyn Scene>;
+
+fn init_boxed<S: Scene + 'static>(context: &Context) -> Box<dyn Scene> {
+    Box::new(S::init(context))
+}
+
+/// The scenes a demo module can run, by name.
+#[derive(Default)]
+pub struct Registry {
+    scenes: Vec<(&'static str, InitScene)>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `S` as `name`. The first scene added is the default one.
+    pub fn with<S: Scene + 'static>(mut self, name: &'static str) -> Self {
+        assert!(
+            self.get(Some(name)).is_err(),
+            "scene {name:?} is registered twice"
+        );
+        self.scenes.push((name, init_boxed::<S>));
+        self
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.scenes.iter().map(|(name, _)| *name)
+    }
+
+    /// Finds the scene called `name`, or the default one for `None`.
+    pub fn get(&self, name: Option<&str>) -> Result<InitScene, String> {
+        let found = match name {
+            Some(name) => self.scenes.iter().find(|(scene, _)| *scene == name),
+            None => self.scenes.first(),
+        };
+        found.map(|(_, init)| *init).ok_or_else(|| {
+            format!(
+                "Unknown scene {:?}, expected one of: {}",
+                name.unwrap_or_default(),
+                self.names().collect::<Vec<_>>().join(", ")
+            )
+        })
+    }
+}
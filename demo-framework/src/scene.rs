@@ -0,0 +1,35 @@
+use crate::{Context, ShaderDiagnostic};
+
+/// One visual of a demo module.
+pub trait Scene {
+    /// Creates the pipelines and buffers of the scene.
+    fn init(context: &Context) -> Self
+    where
+        Self: Sized;
+
+    /// Advances the scene, once per frame before [`Scene::render`].
+    fn update(&mut self, _context: &Context) {}
+
+    /// Records the commands drawing a frame to `target`.
+    fn render(
+        &mut self,
+        context: &Context,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+    );
+
+    /// Called when the render target changes size, before the next frame.
+    fn resize(&mut self, _context: &Context, _width: u32, _height: u32) {}
+
+    /// Replaces the shader of scenes that can be edited live, keeping the
+    /// current one if `source` has problems.
+    fn set_shader(
+        &mut self,
+        _context: &Context,
+        _source: &str,
+    ) -> Result<(), Vec<ShaderDiagnostic>> {
+        Err(vec![ShaderDiagnostic::new(
+            "this scene doesn't support changing its shader",
+        )])
+    }
+}
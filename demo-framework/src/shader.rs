@@ -0,0 +1,319 @@
+use std::error::Error;
+
+use crate::Context;
+
+/// A problem in a shader, at a line of the source given to
+/// [`compile_wgsl`], not counting the prelude.
+#[cfg_attr(
+    target_arch = "wasm32",
+    wasm_bindgen::prelude::wasm_bindgen(getter_with_clone)
+)]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShaderDiagnostic {
+    /// 1-based, or `None` when the problem isn't in the source, such as a
+    /// missing entry point.
+    pub line: Option<u32>,
+    /// 1-based, in bytes.
+    pub column: Option<u32>,
+    pub message: String,
+}
+
+impl ShaderDiagnostic {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            line: None,
+            column: None,
+            message: message.into(),
+        }
+    }
+
+    /// Places the diagnostic at `location` of `prelude` followed by the source.
+    fn at(mut self, location: Option<naga::SourceLocation>, prelude: &str) -> Self {
+        let prelude_lines = prelude.lines().count() as u32;
+        if let Some(location) = location {
+            if location.line_number > prelude_lines {
+                self.line = Some(location.line_number - prelude_lines);
+                self.column = Some(location.line_position);
+            }
+        }
+        self
+    }
+}
+
+/// `error` with the errors that caused it, as naga nests the useful part.
+fn error_chain(error: &dyn Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(error) = source {
+        message += &format!(": {error}");
+        source = error.source();
+    }
+    message
+}
+
+/// What the pipeline a shader goes into gives it. A shader that doesn't fit
+/// is valid WGSL, but `create_render_pipeline` rejects it with an uncaptured
+/// error, which brings the whole module down.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PipelineInterface<'a> {
+    /// The stages and names of the entry points the pipeline runs.
+    pub entry_points: &'a [(naga::ShaderStage, &'a str)],
+    /// The entries of the bind group layouts of the pipeline, by group.
+    pub bind_groups: &'a [&'a [wgpu::BindGroupLayoutEntry]],
+    /// The formats of the color targets of the fragment stage, by location.
+    pub color_targets: &'a [wgpu::TextureFormat],
+}
+
+/// Whether a global in `space` of type `inner` can be bound to `ty`.
+fn binding_fits(
+    space: naga::AddressSpace,
+    inner: &naga::TypeInner,
+    ty: &wgpu::BindingType,
+) -> bool {
+    match (ty, space) {
+        (
+            wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                ..
+            },
+            naga::AddressSpace::Uniform,
+        ) => true,
+        (
+            wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only },
+                ..
+            },
+            naga::AddressSpace::Storage { access },
+        ) => !read_only || !access.contains(naga::StorageAccess::STORE),
+        (wgpu::BindingType::Sampler(_), naga::AddressSpace::Handle) => {
+            matches!(inner, naga::TypeInner::Sampler { .. })
+        }
+        (
+            wgpu::BindingType::Texture { .. } | wgpu::BindingType::StorageTexture { .. },
+            naga::AddressSpace::Handle,
+        ) => matches!(inner, naga::TypeInner::Image { .. }),
+        _ => false,
+    }
+}
+
+/// Checks the resources the entry points use are in `interface`, with a type
+/// that fits, and that fragment entry points write what the color targets
+/// take.
+fn check_interface(
+    module: &naga::Module,
+    info: &naga::valid::ModuleInfo,
+    interface: &PipelineInterface,
+    full_source: &str,
+    prelude: &str,
+) -> Vec<ShaderDiagnostic> {
+    let mut diagnostics = vec![];
+    for (index, entry_point) in module.entry_points.iter().enumerate() {
+        if !interface
+            .entry_points
+            .iter()
+            .any(|(stage, name)| entry_point.stage == *stage && entry_point.name == *name)
+        {
+            continue;
+        }
+        let stage = match entry_point.stage {
+            naga::ShaderStage::Vertex => wgpu::ShaderStages::VERTEX,
+            naga::ShaderStage::Fragment => wgpu::ShaderStages::FRAGMENT,
+            naga::ShaderStage::Compute => wgpu::ShaderStages::COMPUTE,
+        };
+
+        let function_info = info.get_entry_point(index);
+        for (handle, global) in module.global_variables.iter() {
+            let Some(binding) = &global.binding else {
+                continue;
+            };
+            if function_info[handle].is_empty() {
+                continue;
+            }
+            let name = global.name.as_deref().unwrap_or("a global");
+            let entry = interface
+                .bind_groups
+                .get(binding.group as usize)
+                .and_then(|entries| {
+                    entries
+                        .iter()
+                        .find(|entry| entry.binding == binding.binding)
+                })
+                .filter(|entry| entry.visibility.contains(stage));
+            let inner = &module.types[global.ty].inner;
+            let problem = match entry {
+                None => Some(format!(
+                    "`{name}` is at @group({}) @binding({}), which the pipeline doesn't have",
+                    binding.group, binding.binding
+                )),
+                Some(entry) if !binding_fits(global.space, inner, &entry.ty) => Some(format!(
+                    "`{name}` doesn't match the type of @group({}) @binding({}) in the pipeline",
+                    binding.group, binding.binding
+                )),
+                Some(wgpu::BindGroupLayoutEntry {
+                    ty:
+                        wgpu::BindingType::Buffer {
+                            min_binding_size: Some(size),
+                            ..
+                        },
+                    ..
+                }) if inner.size(module.to_ctx()) as u64 > size.get() => Some(format!(
+                    "`{name}` is bigger than the {size} bytes of @group({}) @binding({})",
+                    binding.group, binding.binding
+                )),
+                Some(_) => None,
+            };
+            if let Some(problem) = problem {
+                let span = module.global_variables.get_span(handle);
+                let location = span.is_defined().then(|| span.location(full_source));
+                diagnostics.push(ShaderDiagnostic::new(problem).at(location, prelude));
+            }
+        }
+
+        if entry_point.stage == naga::ShaderStage::Fragment {
+            diagnostics.extend(check_fragment_outputs(module, entry_point, interface));
+        }
+    }
+    diagnostics
+}
+
+/// The outputs of the fragment `entry_point` by location, with their type.
+fn fragment_outputs<'a>(
+    module: &'a naga::Module,
+    entry_point: &naga::EntryPoint,
+) -> Vec<(u32, &'a naga::TypeInner)> {
+    let location = |binding: &Option<naga::Binding>| match binding {
+        Some(naga::Binding::Location { location, .. }) => Some(*location),
+        _ => None,
+    };
+    let Some(result) = &entry_point.function.result else {
+        return vec![];
+    };
+    match &module.types[result.ty].inner {
+        naga::TypeInner::Struct { members, .. } if result.binding.is_none() => members
+            .iter()
+            .filter_map(|member| Some((location(&member.binding)?, &module.types[member.ty].inner)))
+            .collect(),
+        inner => location(&result.binding)
+            .map(|location| (location, inner))
+            .into_iter()
+            .collect(),
+    }
+}
+
+/// Checks every color target gets a value with enough components of the kind
+/// its format stores.
+fn check_fragment_outputs(
+    module: &naga::Module,
+    entry_point: &naga::EntryPoint,
+    interface: &PipelineInterface,
+) -> Vec<ShaderDiagnostic> {
+    let outputs = fragment_outputs(module, entry_point);
+    let name = &entry_point.name;
+    let mut diagnostics = vec![];
+    for (location, format) in interface.color_targets.iter().enumerate() {
+        let Some((_, inner)) = outputs
+            .iter()
+            .find(|(output, _)| *output as usize == location)
+        else {
+            diagnostics.push(ShaderDiagnostic::new(format!(
+                "`{name}` has to return a value at @location({location})"
+            )));
+            continue;
+        };
+        let (components, kind) = match inner {
+            naga::TypeInner::Scalar(scalar) => (1, Some(scalar.kind)),
+            naga::TypeInner::Vector { size, scalar } => (*size as u8, Some(scalar.kind)),
+            _ => (0, None),
+        };
+        let expected_kind = match format.sample_type(None, None) {
+            Some(wgpu::TextureSampleType::Sint) => naga::ScalarKind::Sint,
+            Some(wgpu::TextureSampleType::Uint) => naga::ScalarKind::Uint,
+            _ => naga::ScalarKind::Float,
+        };
+        if kind != Some(expected_kind) || components < format.components() {
+            let kind = match expected_kind {
+                naga::ScalarKind::Sint => "i32",
+                naga::ScalarKind::Uint => "u32",
+                _ => "f32",
+            };
+            diagnostics.push(ShaderDiagnostic::new(format!(
+                "`{name}` has to return a vec{}<{kind}> at @location({location}) for the {format:?} target",
+                format.components()
+            )));
+        }
+    }
+    for (location, _) in outputs {
+        if location as usize >= interface.color_targets.len() {
+            diagnostics.push(ShaderDiagnostic::new(format!(
+                "`{name}` returns a value at @location({location}), which the pipeline has no target for"
+            )));
+        }
+    }
+    diagnostics
+}
+
+/// Checks the WGSL `source`, appended to the code of the scene in `prelude`,
+/// and that it fits the pipeline described by `interface`, before turning it
+/// into a shader module.
+///
+/// Unlike `create_shader_module` and `create_render_pipeline`, which report
+/// problems asynchronously, this gives them back with their line so editors
+/// can show them.
+pub fn compile_wgsl(
+    context: &Context,
+    label: &str,
+    prelude: &str,
+    source: &str,
+    interface: &PipelineInterface,
+) -> Result<wgpu::ShaderModule, Vec<ShaderDiagnostic>> {
+    // The prelude ends with a line break so line numbers add up
+    let prelude = if prelude.is_empty() || prelude.ends_with('\n') {
+        prelude.to_string()
+    } else {
+        format!("{prelude}\n")
+    };
+    let full_source = format!("{prelude}{source}");
+
+    let module = naga::front::wgsl::parse_str(&full_source).map_err(|err| {
+        vec![ShaderDiagnostic::new(err.message()).at(err.location(&full_source), &prelude)]
+    })?;
+
+    let missing = interface
+        .entry_points
+        .iter()
+        .filter(|(stage, name)| {
+            !module
+                .entry_points
+                .iter()
+                .any(|entry_point| entry_point.stage == *stage && entry_point.name == *name)
+        })
+        .map(|(stage, name)| {
+            ShaderDiagnostic::new(format!("expected a {stage:?} entry point `{name}`"))
+        })
+        .collect::<Vec<_>>();
+    if !missing.is_empty() {
+        return Err(missing);
+    }
+
+    let info = naga::valid::Validator::new(
+        naga::valid::ValidationFlags::all(),
+        naga::valid::Capabilities::default(),
+    )
+    .validate(&module)
+    .map_err(|err| {
+        vec![ShaderDiagnostic::new(error_chain(err.as_inner()))
+            .at(err.location(&full_source), &prelude)]
+    })?;
+
+    let mismatches = check_interface(&module, &info, interface, &full_source, &prelude);
+    if !mismatches.is_empty() {
+        return Err(mismatches);
+    }
+
+    Ok(context
+        .device
+        .create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(label),
+            source: wgpu::ShaderSource::Wgsl(full_source.into()),
+        }))
+}
@@ -0,0 +1,193 @@
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+use wasm_bindgen::prelude::*;
+
+use crate::Context;
+
+/// How many frames the averages cover, about a second.
+const WINDOW: usize = 60;
+
+/// How fast a demo renders, as [`crate::DemoHandle::stats`] gives it to the
+/// page.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FrameStats {
+    /// Milliseconds between frames, averaged over the last ones.
+    pub frame_time: f64,
+    /// Frames per second, from `frame_time`.
+    pub fps: f64,
+    /// Milliseconds the GPU spent drawing a recent frame, when the adapter
+    /// supports timestamp queries.
+    pub gpu_time: Option<f64>,
+    /// Frames rendered since the demo started.
+    pub frames: u32,
+}
+
+/// Averages the time between frames.
+#[derive(Default)]
+pub(crate) struct FrameTimer {
+    intervals: VecDeque<f64>,
+    total: f64,
+    frames: u32,
+}
+
+impl FrameTimer {
+    /// Counts a frame, rendered `interval` milliseconds after the previous
+    /// one, or `None` if it's the first one since the loop was paused.
+    pub(crate) fn record(&mut self, interval: Option<f64>) {
+        self.frames += 1;
+        if let Some(interval) = interval {
+            self.intervals.push_back(interval);
+            self.total += interval;
+            if self.intervals.len() > WINDOW {
+                self.total -= self.intervals.pop_front().unwrap_or_default();
+            }
+        }
+    }
+
+    pub(crate) fn stats(&self, gpu_time: Option<f64>) -> FrameStats {
+        let frame_time = if self.intervals.is_empty() {
+            0.0
+        } else {
+            self.total / self.intervals.len() as f64
+        };
+        FrameStats {
+            frame_time,
+            fps: if frame_time > 0.0 {
+                1000.0 / frame_time
+            } else {
+                0.0
+            },
+            gpu_time,
+            frames: self.frames,
+        }
+    }
+}
+
+/// Where the readback buffer of a [`GpuTimer`] is at.
+#[derive(Clone, Copy, PartialEq)]
+enum Readback {
+    /// Unmapped, so it can take a new measurement.
+    Idle,
+    Mapping,
+    Mapped,
+}
+
+/// Measures how long the GPU takes to run the commands of a frame, with
+/// timestamps written by empty compute passes around them. This keeps scenes
+/// unaware of it, as the timestamps of a render pass are set by whoever
+/// begins it.
+///
+/// Only one measurement is in flight at a time, so on slow readbacks some
+/// frames go unmeasured.
+pub(crate) struct GpuTimer {
+    queries: wgpu::QuerySet,
+    resolve: wgpu::Buffer,
+    readback: wgpu::Buffer,
+    state: Rc<Cell<Readback>>,
+    /// Nanoseconds per timestamp tick.
+    period: f32,
+    last: Option<f64>,
+}
+
+impl GpuTimer {
+    const SIZE: u64 = 2 * std::mem::size_of::<u64>() as u64;
+
+    /// The timer of `context`, if its device has
+    /// [`wgpu::Features::TIMESTAMP_QUERY`] enabled.
+    pub(crate) fn new(context: &Context) -> Option<Self> {
+        if !context
+            .device
+            .features()
+            .contains(wgpu::Features::TIMESTAMP_QUERY)
+        {
+            return None;
+        }
+
+        let queries = context.device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some("GPU timer"),
+            ty: wgpu::QueryType::Timestamp,
+            count: 2,
+        });
+        let resolve = context.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("GPU timer resolve"),
+            size: Self::SIZE,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readback = context.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("GPU timer readback"),
+            size: Self::SIZE,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        Some(Self {
+            queries,
+            resolve,
+            readback,
+            state: Rc::new(Cell::new(Readback::Idle)),
+            period: context.queue.get_timestamp_period(),
+            last: None,
+        })
+    }
+
+    fn timestamp(&self, encoder: &mut wgpu::CommandEncoder, index: u32) {
+        encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("GPU timer"),
+            timestamp_writes: Some(wgpu::ComputePassTimestampWrites {
+                query_set: &self.queries,
+                beginning_of_pass_write_index: Some(index),
+                end_of_pass_write_index: None,
+            }),
+        });
+    }
+
+    /// Starts measuring the commands recorded next, unless the previous
+    /// measurement wasn't read yet. Returns whether it did.
+    pub(crate) fn begin(&self, encoder: &mut wgpu::CommandEncoder) -> bool {
+        if self.state.get() != Readback::Idle {
+            return false;
+        }
+        self.timestamp(encoder, 0);
+        true
+    }
+
+    /// Ends the measurement [`GpuTimer::begin`] started.
+    pub(crate) fn end(&self, encoder: &mut wgpu::CommandEncoder) {
+        self.timestamp(encoder, 1);
+        encoder.resolve_query_set(&self.queries, 0..2, &self.resolve, 0);
+        encoder.copy_buffer_to_buffer(&self.resolve, 0, &self.readback, 0, Self::SIZE);
+    }
+
+    /// Reads the measurement back once the commands ending it are submitted.
+    pub(crate) fn map(&self) {
+        self.state.set(Readback::Mapping);
+        let state = self.state.clone();
+        self.readback
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                state.set(match result {
+                    Ok(()) => Readback::Mapped,
+                    Err(_) => Readback::Idle,
+                });
+            });
+    }
+
+    /// The latest measurement, in milliseconds.
+    pub(crate) fn gpu_time(&mut self) -> Option<f64> {
+        if self.state.get() == Readback::Mapped {
+            {
+                let data = self.readback.slice(..).get_mapped_range();
+                let timestamps: [u64; 2] = bytemuck::pod_read_unaligned(&data);
+                let ticks = timestamps[1].saturating_sub(timestamps[0]);
+                self.last = Some(ticks as f64 * self.period as f64 / 1_000_000.0);
+            }
+            self.readback.unmap();
+            self.state.set(Readback::Idle);
+        }
+        self.last
+    }
+}
@@ -0,0 +1,273 @@
+use std::rc::Rc;
+
+use log::*;
+use wasm_bindgen::prelude::*;
+
+use crate::handle::{self, Controls, DemoHandle, Watchers};
+use crate::stats::{FrameTimer, GpuTimer};
+use crate::{Context, Registry, Scene};
+
+async fn request_animation_frame(window: &web_sys::Window) -> Result<(), String> {
+    let (s, r) = oneshot::channel();
+
+    let closure = web_sys::wasm_bindgen::prelude::Closure::once(move || s.send(()).unwrap());
+
+    window
+        .request_animation_frame(closure.as_ref().unchecked_ref())
+        .map_err(|err| format!("{:?}", err.as_string()))?;
+
+    r.await.map_err(|err| format!("{err}"))?;
+    Ok(())
+}
+
+fn adapter_options<'a>(
+    surface: Option<&'a wgpu::Surface<'static>>,
+) -> wgpu::RequestAdapterOptions<'a, 'static> {
+    wgpu::RequestAdapterOptions {
+        power_preference: wgpu::PowerPreference::default(),
+        force_fallback_adapter: false,
+        // Request an adapter which can render to our surface
+        compatible_surface: surface,
+    }
+}
+
+/// Finds an adapter able to render to `canvas`, trying WebGPU first and then
+/// WebGL2 if the `webgl` feature is enabled.
+async fn request_adapter(
+    canvas: &web_sys::HtmlCanvasElement,
+) -> Result<(wgpu::Surface<'static>, wgpu::Adapter), String> {
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+        backends: wgpu::Backends::BROWSER_WEBGPU,
+        ..Default::default()
+    });
+    // Creating a surface claims the canvas for one backend, so check there is
+    // a WebGPU adapter at all before doing so.
+    if instance
+        .request_adapter(&adapter_options(None))
+        .await
+        .is_some()
+    {
+        let surface = instance
+            .create_surface(wgpu::SurfaceTarget::Canvas(canvas.clone()))
+            .map_err(|err| format!("{err}"))?;
+        return match instance
+            .request_adapter(&adapter_options(Some(&surface)))
+            .await
+        {
+            Some(adapter) => Ok((surface, adapter)),
+            None => Err("Failed to find an appropriate adapter".to_string()),
+        };
+    }
+
+    #[cfg(feature = "webgl")]
+    {
+        info!("WebGPU isn't available, falling back to WebGL2");
+        let instance = wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends: wgpu::Backends::GL,
+            ..Default::default()
+        });
+        let surface = instance
+            .create_surface(wgpu::SurfaceTarget::Canvas(canvas.clone()))
+            .map_err(|err| format!("{err}"))?;
+        if let Some(adapter) = instance
+            .request_adapter(&adapter_options(Some(&surface)))
+            .await
+        {
+            return Ok((surface, adapter));
+        }
+    }
+
+    Err("Failed to find an appropriate adapter".to_string())
+}
+
+/// A scene drawing to a canvas.
+struct Runner {
+    canvas: web_sys::HtmlCanvasElement,
+    surface: wgpu::Surface<'static>,
+    adapter: wgpu::Adapter,
+    context: Context,
+    scene: Box<dyn Scene>,
+    configured_size: Option<(u32, u32)>,
+    frame_timer: FrameTimer,
+    gpu_timer: Option<GpuTimer>,
+}
+
+impl Runner {
+    fn configure_surface(&self, width: u32, height: u32) -> Result<(), String> {
+        let config = self
+            .surface
+            .get_default_config(&self.adapter, width, height)
+            .ok_or("Failed to get default config")?;
+        self.surface.configure(&self.context.device, &config);
+        Ok(())
+    }
+
+    /// Renders a frame, or skips it when the surface can't give one right now.
+    fn render(&mut self, controls: &Controls, delta_time: f32) -> Result<(), String> {
+        let (width, height) = controls.size(self.context.device.limits().max_texture_dimension_2d);
+
+        if self.configured_size != Some((width, height)) {
+            self.configured_size = Some((width, height));
+            self.configure_surface(width, height)?;
+
+            self.canvas.set_width(width);
+            self.canvas.set_height(height);
+            self.scene.resize(&self.context, width, height);
+        }
+
+        let frame = match self.surface.get_current_texture() {
+            Ok(frame) => frame,
+            // Routine while resizing or when the tab comes back to the foreground
+            Err(wgpu::SurfaceError::Outdated | wgpu::SurfaceError::Lost) => {
+                debug!("Reconfiguring the surface, which is outdated or lost");
+                return self.configure_surface(width, height);
+            }
+            Err(wgpu::SurfaceError::Timeout) => {
+                debug!("Timed out acquiring a frame, skipping it");
+                return Ok(());
+            }
+            Err(err @ wgpu::SurfaceError::OutOfMemory) => return Err(format!("{err}")),
+        };
+
+        let time = self.context.uniforms().time + delta_time;
+        self.context.set_uniforms(crate::GlobalUniforms {
+            resolution: [width as f32, height as f32],
+            mouse: controls.mouse(),
+            time,
+            delta_time,
+        });
+        self.scene.update(&self.context);
+
+        let view = frame
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        let mut encoder = self
+            .context
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        let timing = match &self.gpu_timer {
+            Some(timer) if timer.begin(&mut encoder) => Some(timer),
+            _ => None,
+        };
+        self.scene.render(&self.context, &mut encoder, &view);
+        if let Some(timer) = timing {
+            timer.end(&mut encoder);
+        }
+
+        self.context.queue.submit(Some(encoder.finish()));
+        if let Some(timer) = timing {
+            timer.map();
+        }
+        frame.present();
+        Ok(())
+    }
+
+    async fn render_loop(
+        mut self,
+        window: web_sys::Window,
+        controls: Rc<Controls>,
+    ) -> Result<(), String> {
+        let performance = window.performance().ok_or("Failed to get performance")?;
+        // When the previous frame was rendered, in milliseconds
+        let mut last_frame = None;
+        while !controls.is_stopped() {
+            if !self.canvas.is_connected() {
+                info!("Canvas removed from the page, stopping");
+                break;
+            }
+            if let Some((source, reply)) = controls.take_shader() {
+                let _ = reply.send(self.scene.set_shader(&self.context, &source));
+            }
+            if !controls.should_render() {
+                controls.wait_until_woken().await;
+                // The scene doesn't move while paused
+                last_frame = None;
+                continue;
+            }
+
+            let now = performance.now();
+            let interval = last_frame.map(|last| now - last);
+            last_frame = Some(now);
+            self.render(&controls, interval.unwrap_or(0.0) as f32 / 1000.0)?;
+
+            self.frame_timer.record(interval);
+            let gpu_time = self.gpu_timer.as_mut().and_then(GpuTimer::gpu_time);
+            controls.set_stats(self.frame_timer.stats(gpu_time));
+
+            request_animation_frame(&window).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Starts rendering `scene` of `registry`, or its default scene, to the
+/// canvas with the id `canvas_id`, returning a handle to control the render
+/// loop.
+pub async fn run(
+    canvas_id: &str,
+    registry: &Registry,
+    scene: Option<&str>,
+) -> Result<DemoHandle, JsValue> {
+    let init_scene = registry.get(scene)?;
+
+    let window = web_sys::window().ok_or("Failed to get window")?;
+
+    let document = window.document().ok_or("Failed to get document")?;
+    let html_canvas: web_sys::HtmlCanvasElement = document
+        .get_element_by_id(canvas_id)
+        .ok_or("Failed to get canvas")?
+        .dyn_into::<web_sys::HtmlCanvasElement>()?;
+
+    let (surface, adapter) = request_adapter(&html_canvas).await?;
+    let backend = adapter.get_info().backend;
+    info!("Rendering with {}", handle::backend_name(backend));
+
+    // Create the logical device and command queue
+    let (device, queue) = adapter
+        .request_device(
+            &wgpu::DeviceDescriptor {
+                label: None,
+                // For the GPU time of the stats, where available
+                required_features: adapter.features() & wgpu::Features::TIMESTAMP_QUERY,
+                // Make sure we use the texture resolution limits from the adapter, so we can support images the size of the swapchain.
+                required_limits: wgpu::Limits::downlevel_webgl2_defaults()
+                    .using_resolution(adapter.limits()),
+                memory_hints: wgpu::MemoryHints::MemoryUsage,
+            },
+            None,
+        )
+        .await
+        .map_err(|err| format!("{err}"))?;
+
+    let swapchain_capabilities = surface.get_capabilities(&adapter);
+    let swapchain_format = swapchain_capabilities.formats[0];
+
+    let context = Context::new(device, queue, swapchain_format);
+    let scene = init_scene(&context);
+    let gpu_timer = GpuTimer::new(&context);
+
+    let runner = Runner {
+        canvas: html_canvas.clone(),
+        surface,
+        adapter,
+        context,
+        scene,
+        configured_size: None,
+        frame_timer: FrameTimer::default(),
+        gpu_timer,
+    };
+
+    let controls = Rc::new(Controls::default());
+    let watchers = Watchers::new(&html_canvas, document, &controls)?;
+    wasm_bindgen_futures::spawn_local({
+        let controls = controls.clone();
+        async move {
+            if let Err(err) = runner.render_loop(window, controls).await {
+                error!("Render loop failed: {err}");
+            }
+            drop(watchers);
+        }
+    });
+
+    Ok(DemoHandle::new(controls, backend))
+}
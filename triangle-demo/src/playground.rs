@@ -0,0 +1,116 @@
+use demo_framework::{
+    compile_wgsl, Context, PipelineInterface, Scene, ShaderDiagnostic, ShaderStage, GLOBALS_WGSL,
+};
+
+/// The fragment shader the playground starts with.
+pub const DEFAULT_SHADER: &str = include_str!("playground.wgsl");
+
+/// Covers the target with one triangle, so the fragment shader runs for every
+/// pixel.
+const VERTEX_WGSL: &str = "@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    let corner = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    return vec4<f32>(corner * 2.0 - 1.0, 0.0, 1.0);
+}
+";
+
+/// A fragment shader over the whole canvas, which the shader playground
+/// replaces as it's edited.
+pub struct Playground {
+    pipeline_layout: wgpu::PipelineLayout,
+    render_pipeline: wgpu::RenderPipeline,
+}
+
+impl Playground {
+    fn create_pipeline(
+        context: &Context,
+        pipeline_layout: &wgpu::PipelineLayout,
+        source: &str,
+    ) -> Result<wgpu::RenderPipeline, Vec<ShaderDiagnostic>> {
+        let shader = compile_wgsl(
+            context,
+            "playground",
+            &format!("{GLOBALS_WGSL}\n{VERTEX_WGSL}"),
+            source,
+            &PipelineInterface {
+                entry_points: &[(ShaderStage::Fragment, "fs_main")],
+                bind_groups: &[context.globals_layout_entries()],
+                color_targets: &[context.format],
+            },
+        )?;
+
+        Ok(context
+            .device
+            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("playground"),
+                layout: Some(pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: Some("vs_main"),
+                    buffers: &[],
+                    compilation_options: Default::default(),
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: Some("fs_main"),
+                    compilation_options: Default::default(),
+                    targets: &[Some(context.format.into())],
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+                cache: None,
+            }))
+    }
+}
+
+impl Scene for Playground {
+    fn init(context: &Context) -> Self {
+        let pipeline_layout =
+            context
+                .device
+                .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                    label: Some("playground"),
+                    bind_group_layouts: &[context.globals_layout()],
+                    push_constant_ranges: &[],
+                });
+        let render_pipeline = Self::create_pipeline(context, &pipeline_layout, DEFAULT_SHADER)
+            .expect("the default shader of the playground is invalid");
+
+        Self {
+            pipeline_layout,
+            render_pipeline,
+        }
+    }
+
+    fn render(
+        &mut self,
+        context: &Context,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+    ) {
+        let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("playground"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        rpass.set_pipeline(&self.render_pipeline);
+        rpass.set_bind_group(0, context.globals_bind_group(), &[]);
+        rpass.draw(0..3, 0..1);
+    }
+
+    fn set_shader(&mut self, context: &Context, source: &str) -> Result<(), Vec<ShaderDiagnostic>> {
+        self.render_pipeline = Self::create_pipeline(context, &self.pipeline_layout, source)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,8 @@
+// Runs for every pixel of the canvas, `position` being its coordinates.
+// `globals.time`, `globals.resolution` and `globals.mouse` change every frame.
+@fragment
+fn fs_main(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let uv = position.xy / globals.resolution;
+    let color = 0.5 + 0.5 * cos(globals.time + uv.xyx + vec3<f32>(0.0, 2.0, 4.0));
+    return vec4<f32>(color, 1.0);
+}
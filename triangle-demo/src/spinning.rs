@@ -0,0 +1,125 @@
+use demo_framework::{Context, Scene, GLOBALS_WGSL};
+use wgpu::util::DeviceExt;
+
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct Vertex {
+    position: [f32; 2],
+    color: [f32; 3],
+}
+
+const VERTICES: &[Vertex] = &[
+    Vertex {
+        position: [0.0, 0.8],
+        color: [1.0, 0.0, 0.0],
+    },
+    Vertex {
+        position: [-0.7, -0.4],
+        color: [0.0, 1.0, 0.0],
+    },
+    Vertex {
+        position: [0.7, -0.4],
+        color: [0.0, 0.0, 1.0],
+    },
+];
+
+/// A triangle with a color per corner, turning over time and lighting up
+/// under the pointer.
+pub struct SpinningTriangle {
+    render_pipeline: wgpu::RenderPipeline,
+    vertices: wgpu::Buffer,
+}
+
+impl Scene for SpinningTriangle {
+    fn init(context: &Context) -> Self {
+        let source = format!("{GLOBALS_WGSL}\n{}", include_str!("spinning.wgsl"));
+        let shader = context
+            .device
+            .create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("spinning"),
+                source: wgpu::ShaderSource::Wgsl(source.into()),
+            });
+
+        let pipeline_layout =
+            context
+                .device
+                .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                    label: Some("spinning"),
+                    bind_group_layouts: &[context.globals_layout()],
+                    push_constant_ranges: &[],
+                });
+
+        let render_pipeline =
+            context
+                .device
+                .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                    label: Some("spinning"),
+                    layout: Some(&pipeline_layout),
+                    vertex: wgpu::VertexState {
+                        module: &shader,
+                        entry_point: Some("vs_main"),
+                        buffers: &[wgpu::VertexBufferLayout {
+                            array_stride: std::mem::size_of::<Vertex>() as u64,
+                            step_mode: wgpu::VertexStepMode::Vertex,
+                            attributes: &wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x3],
+                        }],
+                        compilation_options: Default::default(),
+                    },
+                    fragment: Some(wgpu::FragmentState {
+                        module: &shader,
+                        entry_point: Some("fs_main"),
+                        compilation_options: Default::default(),
+                        targets: &[Some(context.format.into())],
+                    }),
+                    primitive: wgpu::PrimitiveState::default(),
+                    depth_stencil: None,
+                    multisample: wgpu::MultisampleState::default(),
+                    multiview: None,
+                    cache: None,
+                });
+
+        let vertices = context
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("spinning vertices"),
+                contents: bytemuck::cast_slice(VERTICES),
+                usage: wgpu::BufferUsages::VERTEX,
+            });
+
+        Self {
+            render_pipeline,
+            vertices,
+        }
+    }
+
+    fn render(
+        &mut self,
+        context: &Context,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+    ) {
+        let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("spinning"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color {
+                        r: 0.05,
+                        g: 0.05,
+                        b: 0.08,
+                        a: 1.0,
+                    }),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        rpass.set_pipeline(&self.render_pipeline);
+        rpass.set_bind_group(0, context.globals_bind_group(), &[]);
+        rpass.set_vertex_buffer(0, self.vertices.slice(..));
+        rpass.draw(0..VERTICES.len() as u32, 0..1);
+    }
+}
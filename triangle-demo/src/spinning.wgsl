@@ -0,0 +1,33 @@
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) color: vec3<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec3<f32>,
+}
+
+@vertex
+fn vs_main(in: VertexInput) -> VertexOutput {
+    let angle = globals.time;
+    let rotated = vec2<f32>(
+        in.position.x * cos(angle) - in.position.y * sin(angle),
+        in.position.x * sin(angle) + in.position.y * cos(angle),
+    );
+    // Keep the proportions of the triangle whatever the shape of the canvas
+    let aspect = globals.resolution.x / max(globals.resolution.y, 1.0);
+
+    var out: VertexOutput;
+    out.position = vec4<f32>(rotated.x / max(aspect, 1.0), rotated.y * min(aspect, 1.0), 0.0, 1.0);
+    out.color = in.color;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    // Lighter close to the pointer
+    let distance_to_mouse = distance(in.position.xy, globals.mouse) / max(globals.resolution.y, 1.0);
+    let glow = 1.0 - min(distance_to_mouse * 4.0, 1.0);
+    return vec4<f32>(in.color + glow * 0.5, 1.0);
+}
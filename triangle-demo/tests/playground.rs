@@ -0,0 +1,151 @@
+//! Checks the shader playground reports problems at the lines of the edited
+//! shader, not of the code the scene puts before it.
+
+// Headless rendering is native only
+#![cfg(not(target_arch = "wasm32"))]
+
+use demo_framework::Headless;
+
+fn set_shader(source: &str) -> Vec<(Option<u32>, String)> {
+    let headless = Headless::new(16, 16)
+        .expect("no adapter to render with, install a software one such as lavapipe or llvmpipe");
+    let registry = triangle_demo::registry();
+    let mut scene = headless.init(registry.get(Some("playground")).unwrap());
+    match scene.set_shader(headless.context(), source) {
+        Ok(()) => vec![],
+        Err(diagnostics) => diagnostics
+            .into_iter()
+            .map(|diagnostic| (diagnostic.line, diagnostic.message))
+            .collect(),
+    }
+}
+
+#[test]
+fn valid_shader_is_accepted() {
+    let source = "@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+    return vec4<f32>(globals.time, 0.0, 0.0, 1.0);
+}
+";
+    assert_eq!(set_shader(source), vec![]);
+}
+
+#[test]
+fn syntax_errors_have_the_line_of_the_source() {
+    let source = "@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+    let color = vec4<f32>(1.0, 0.0, 0.0, 1.0)
+    return color;
+}
+";
+    let diagnostics = set_shader(source);
+    assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+    assert_eq!(diagnostics[0].0, Some(4), "{diagnostics:?}");
+}
+
+#[test]
+fn validation_errors_have_the_line_of_the_source() {
+    let source = "@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+    return 1.0;
+}
+";
+    let diagnostics = set_shader(source);
+    assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+    assert_eq!(diagnostics[0].0, Some(3), "{diagnostics:?}");
+}
+
+#[test]
+fn missing_entry_point_has_no_line() {
+    let diagnostics = set_shader("fn helper() {}\n");
+    assert_eq!(
+        diagnostics,
+        vec![(
+            None,
+            "expected a Fragment entry point `fs_main`".to_string()
+        )]
+    );
+}
+
+#[test]
+fn bindings_missing_from_the_pipeline_are_rejected() {
+    let source = "@group(1) @binding(0)
+var<uniform> extra: f32;
+
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+    return vec4<f32>(extra, 0.0, 0.0, 1.0);
+}
+";
+    assert_eq!(
+        set_shader(source),
+        vec![(
+            Some(2),
+            "`extra` is at @group(1) @binding(0), which the pipeline doesn't have".to_string()
+        )]
+    );
+}
+
+#[test]
+fn bindings_of_another_type_are_rejected() {
+    let source = "@group(0) @binding(0)
+var<storage> data: array<f32>;
+
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+    return vec4<f32>(data[0], 0.0, 0.0, 1.0);
+}
+";
+    let diagnostics = set_shader(source);
+    assert!(!diagnostics.is_empty(), "{diagnostics:?}");
+}
+
+#[test]
+fn outputs_the_target_doesnt_take_are_rejected() {
+    let source = "@fragment
+fn fs_main() -> @location(0) vec4<i32> {
+    return vec4<i32>(1, 0, 0, 1);
+}
+";
+    let diagnostics = set_shader(source);
+    assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+    assert!(
+        diagnostics[0]
+            .1
+            .contains("has to return a vec4<f32> at @location(0)"),
+        "{diagnostics:?}"
+    );
+
+    let source = "@fragment
+fn fs_main() -> @location(1) vec4<f32> {
+    return vec4<f32>(1.0);
+}
+";
+    assert_eq!(set_shader(source).len(), 2);
+}
+
+#[test]
+fn rejected_shaders_keep_the_last_good_one() {
+    let mut headless = Headless::new(16, 16)
+        .expect("no adapter to render with, install a software one such as lavapipe or llvmpipe");
+    let registry = triangle_demo::registry();
+    let mut scene = headless.init(registry.get(Some("playground")).unwrap());
+    let red = "@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+    return vec4<f32>(1.0, 0.0, 0.0, 1.0);
+}
+";
+    scene.set_shader(headless.context(), red).unwrap();
+    let mismatched = "@group(1) @binding(0)
+var<uniform> extra: f32;
+
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+    return vec4<f32>(0.0, extra, 0.0, 1.0);
+}
+";
+    assert!(scene.set_shader(headless.context(), mismatched).is_err());
+
+    let image = headless.render(scene.as_mut(), Default::default()).unwrap();
+    assert_eq!(image.pixels[..4], [255, 0, 0, 255]);
+}
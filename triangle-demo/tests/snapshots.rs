@@ -0,0 +1,93 @@
+//! Renders every scene of the module with a software adapter and compares it
+//! to the reference images in `tests/snapshots`.
+//!
+//! After changing a scene on purpose, run the tests with `UPDATE_SNAPSHOTS=1`
+//! to replace the references with the new images.
+
+// Headless rendering is native only
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::path::PathBuf;
+
+use demo_framework::{GlobalUniforms, Headless, Image};
+
+const WIDTH: u32 = 160;
+const HEIGHT: u32 = 120;
+
+/// How far a channel may be from the reference, for rounding differences.
+const TOLERANCE: u8 = 2;
+/// How many pixels may be further than that, as adapters don't rasterize the
+/// edges of shapes exactly alike.
+const MAX_DIFFERENT_PIXELS: usize = 32;
+
+/// The same uniforms for every scene, so the snapshots don't depend on when
+/// the test runs.
+const UNIFORMS: GlobalUniforms = GlobalUniforms {
+    resolution: [WIDTH as f32, HEIGHT as f32],
+    mouse: [100.0, 40.0],
+    time: 0.5,
+    delta_time: 1.0 / 60.0,
+};
+
+fn snapshot_path(name: &str, suffix: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("snapshots")
+        .join(format!("{name}{suffix}.png"))
+}
+
+/// Compares `image` to the reference of `scene`, writing it next to it as
+/// `<scene>.new.png` to look at when they don't match.
+fn check_snapshot(scene: &str, image: &Image) -> Result<(), String> {
+    let reference_path = snapshot_path(scene, "");
+    let new_path = snapshot_path(scene, ".new");
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        let _ = std::fs::remove_file(&new_path);
+        return image.write_png(&reference_path);
+    }
+
+    let reference = match Image::read_png(&reference_path) {
+        Ok(reference) => reference,
+        Err(err) => {
+            image.write_png(&new_path)?;
+            return Err(format!(
+                "{scene}: no reference ({err}), run with UPDATE_SNAPSHOTS=1 to create it"
+            ));
+        }
+    };
+    let different = image.count_different_pixels(&reference, TOLERANCE);
+    if different > MAX_DIFFERENT_PIXELS {
+        image.write_png(&new_path)?;
+        return Err(format!(
+            "{scene}: {different} pixels differ from {}, see {}",
+            reference_path.display(),
+            new_path.display()
+        ));
+    }
+    let _ = std::fs::remove_file(&new_path);
+    Ok(())
+}
+
+#[test]
+fn scenes_match_snapshots() {
+    let mut headless = Headless::new(WIDTH, HEIGHT)
+        .expect("no adapter to render with, install a software one such as lavapipe or llvmpipe");
+
+    let registry = triangle_demo::registry();
+    let mut failures = vec![];
+    for name in registry.names() {
+        let mut scene = headless.init(registry.get(Some(name)).unwrap());
+        let image = headless.render(scene.as_mut(), UNIFORMS).unwrap();
+        if let Err(err) = check_snapshot(name, &image) {
+            failures.push(err);
+        }
+    }
+    let info = headless.adapter_info();
+    assert!(
+        failures.is_empty(),
+        "rendering with {} ({:?}):\n{}",
+        info.name,
+        info.backend,
+        failures.join("\n")
+    );
+}
@@ -1,98 +1,1072 @@
+use std::collections::HashMap;
 use std::io::Write;
+use std::path::PathBuf;
 use std::{env, fs, path::Path};
 
 use serde::Deserialize;
+use syntect::parsing::SyntaxSet;
 
+#[path = "build/error.rs"]
+mod error;
+#[path = "build/feed.rs"]
+mod feed;
+#[path = "build/headings.rs"]
+mod headings;
+#[path = "build/highlight.rs"]
+mod highlight;
+#[path = "build/shortcodes.rs"]
+mod shortcodes;
+
+use error::{BlogError, BlogErrorKind, BlogErrors};
+
+const INFO_JSON: &str = "blogs/info.json";
+
+/// Legacy description of a post, as found in `blogs/info.json`. Only used as a
+/// fallback for posts that don't carry a front-matter block yet.
 #[derive(Deserialize, Debug)]
-struct BlogEntry {
+struct LegacyBlogEntry {
     blog_date: String, // YYYY-MM-DD
     title: String,
     description: String,
     image_file: String,
+    #[serde(skip)]
+    line: Option<usize>,
 }
 
-fn blog_generator(out_dir: &std::path::Path, blog: &BlogEntry) {
-    let dest_path = out_dir.join(format!("blogs/{}", blog.blog_date));
-    if !dest_path.exists() {
-        fs::create_dir_all(&dest_path).unwrap();
+/// Metadata found at the top of `blogs/<date>/index.md`, either as a YAML block
+/// delimited by `---` or a TOML block delimited by `+++`.
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+struct FrontMatter {
+    title: Option<String>,
+    description: Option<String>,
+    image: Option<String>,
+    date: Option<String>, // YYYY-MM-DD
+    slug: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    draft: bool,
+    #[serde(default)]
+    demos: Vec<Demo>,
+}
+
+/// An interactive wasm module a post embeds, built into `assets/wasm/<module>`.
+/// It's placed with `{{wasm-demo id="..."}}`, or above the post otherwise.
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct Demo {
+    module: String,
+    /// Id of the canvas the module renders to.
+    id: String,
+    /// The scene of the module to run, instead of its default one.
+    scene: Option<String>,
+    /// `<width>/<height>` of the canvas, square by default.
+    aspect_ratio: Option<String>,
+    /// Caps the longest side of the canvas, in device pixels.
+    max_resolution: Option<u32>,
+    /// Shown instead of the canvas when the module fails to start.
+    fallback_image: Option<String>,
+    fallback_text: Option<String>,
+}
+
+impl Demo {
+    /// The width and height of `aspect_ratio`, if it's valid.
+    fn aspect_ratio(&self) -> Option<(u32, u32)> {
+        let Some(aspect_ratio) = &self.aspect_ratio else {
+            return Some((1, 1));
+        };
+        let (width, height) = aspect_ratio.split_once('/')?;
+        let width = width.trim().parse().ok().filter(|&width| width > 0)?;
+        let height = height.trim().parse().ok().filter(|&height| height > 0)?;
+        Some((width, height))
     }
-    let dest_path_file = out_dir.join(format!("blogs/{}/index.html", blog.blog_date));
+}
 
-    let mut file = std::io::BufWriter::new(fs::File::create(&dest_path_file).unwrap());
+/// Checks the `demos` of a post. The problems are reported on the `demos`
+/// line, as the YAML and TOML parsers don't give lines for list items.
+fn check_demos(post: &Path, markdown: &str, crate_dir: &Path, demos: &[Demo]) -> Vec<BlogError> {
+    let mut kinds = vec![];
+    for (index, demo) in demos.iter().enumerate() {
+        let invalid = |message: &str| BlogErrorKind::InvalidDemo {
+            id: demo.id.clone(),
+            message: message.to_string(),
+        };
+        if !is_valid_slug(&demo.id) {
+            kinds.push(invalid(
+                "ids may only have lowercase letters, digits and `-`",
+            ));
+        }
+        if demos[..index].iter().any(|other| other.id == demo.id) {
+            kinds.push(invalid("the id is used by another demo"));
+        }
+        if !shortcodes::is_identifier(&demo.module, |c| c == '-' || c == '_') {
+            kinds.push(invalid(&format!("invalid module name {:?}", demo.module)));
+        }
+        if let Some(scene) = &demo.scene {
+            if !shortcodes::is_identifier(scene, |c| c == '-' || c == '_') {
+                kinds.push(invalid(&format!("invalid scene name {scene:?}")));
+            }
+        }
+        if demo.aspect_ratio().is_none() {
+            kinds.push(invalid(
+                "the aspect ratio should be two positive numbers, like `16/9`",
+            ));
+        }
+        if demo.max_resolution == Some(0) {
+            kinds.push(invalid("the max resolution can't be 0"));
+        }
+        if let Some(image) = &demo.fallback_image {
+            if !crate_dir.join(image).is_file() {
+                kinds.push(BlogErrorKind::MissingImage {
+                    image: image.clone(),
+                });
+            }
+        }
+    }
 
-    let markdown = std::fs::read_to_string(format!("blogs/{}/index.md", blog.blog_date)).unwrap();
-    println!("cargo:rerun-if-changed=blogs/{}/index.md", blog.blog_date);
+    let line = error::line_of_key(markdown, "demos");
+    kinds
+        .into_iter()
+        .map(|kind| BlogError::new(post, kind).at_line(line))
+        .collect()
+}
 
-    let html = markdown::to_html(&markdown);
-    writeln!(file, "{}", html).unwrap();
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct BlogDate {
+    year: u64,
+    month: u8,
+    day: u8,
 }
 
-fn main() {
-    println!("cargo::rerun-if-changed=blogs/info.json");
-    println!("cargo::rerun-if-changed=build.rs");
+impl std::fmt::Display for BlogDate {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
 
-    let entries = fs::read_to_string("blogs/info.json").unwrap();
+fn parse_blog_date(date: &str) -> Option<BlogDate> {
+    let [year, month, day] = date.split('-').collect::<Vec<_>>()[..] else {
+        return None;
+    };
+    let date = BlogDate {
+        year: year.parse().ok()?,
+        month: month.parse().ok()?,
+        day: day.parse().ok()?,
+    };
+    ((1..=12).contains(&date.month) && (1..=31).contains(&date.day)).then_some(date)
+}
 
-    let deserialized: Vec<BlogEntry> = serde_json::from_str(&entries).unwrap();
+/// Turns a title into the lowercase, dash-separated form used in URLs.
+fn slugify(title: &str) -> String {
+    title
+        .to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
 
-    let out_dir = env::var_os("OUT_DIR").unwrap();
-    let out_dir = Path::new(&out_dir);
-    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
-    let crate_dir = Path::new(&crate_dir);
-    let assets_dir = crate_dir.join("assets");
+fn is_valid_slug(slug: &str) -> bool {
+    !slug.is_empty()
+        && slug
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
 
-    let dest_path = out_dir.join("current_blogs.rs");
+/// A post once its front matter and the `info.json` fallback have been merged.
+#[derive(Debug)]
+struct BlogPost {
+    blog_date: BlogDate,
+    slug: String,
+    title: String,
+    description: String,
+    image_file: String,
+    tags: Vec<String>,
+    draft: bool,
+    /// The `index.md` of the post, for error messages.
+    path: PathBuf,
+    /// The markdown, with the shortcodes replaced by markers.
+    body: String,
+    shortcodes: Vec<shortcodes::PlacedShortcode>,
+    demos: Vec<Demo>,
+}
+
+impl BlogPost {
+    /// The site-relative URL of the post, matching the router's.
+    fn route(&self) -> String {
+        format!(
+            "/blog/{}/{}/{}/{}/index.html",
+            self.blog_date.year, self.blog_date.month, self.blog_date.day, self.slug
+        )
+    }
+}
+
+/// Splits the front-matter block from the markdown body, returning `None` if
+/// the file doesn't start with one.
+fn split_front_matter(
+    post: &Path,
+    markdown: &str,
+) -> Result<(Option<FrontMatter>, String), BlogError> {
+    let mut lines = markdown.lines();
+    let delimiter = match lines.next().map(str::trim_end) {
+        Some("---") => "---",
+        Some("+++") => "+++",
+        _ => return Ok((None, markdown.to_string())),
+    };
+
+    let mut block = vec![];
+    let mut closed = false;
+    for line in lines.by_ref() {
+        if line.trim_end() == delimiter {
+            closed = true;
+            break;
+        }
+        block.push(line);
+    }
+    if !closed {
+        return Err(
+            BlogError::new(post, BlogErrorKind::UnclosedFrontMatter { delimiter }).at_line(Some(1)),
+        );
+    }
+    let block = block.join("\n");
+
+    // Lines inside the block are offset by the opening delimiter.
+    let front_matter = if delimiter == "---" {
+        serde_yaml::from_str(&block).map_err(|err| {
+            BlogError::new(
+                post,
+                BlogErrorKind::InvalidFrontMatter {
+                    format: "YAML",
+                    message: err.to_string(),
+                },
+            )
+            .at_line(err.location().map(|location| location.line() + 1))
+        })?
+    } else {
+        toml::from_str(&block).map_err(|err| {
+            BlogError::new(
+                post,
+                BlogErrorKind::InvalidFrontMatter {
+                    format: "TOML",
+                    message: err.message().to_string(),
+                },
+            )
+            .at_line(
+                err.span()
+                    .map(|span| error::line_of_offset(&block, span.start) + 1),
+            )
+        })?
+    };
+
+    Ok((Some(front_matter), lines.collect::<Vec<_>>().join("\n")))
+}
+
+/// Picks a field from the front matter or the `info.json` fallback, failing if
+/// both are present and disagree or if neither has it.
+fn merge_field(
+    post: &Path,
+    markdown: &str,
+    field: &'static str,
+    front: Option<String>,
+    legacy: Option<&String>,
+) -> Result<String, BlogError> {
+    match (front, legacy) {
+        (Some(front), Some(legacy)) if front != *legacy => Err(BlogError::new(
+            post,
+            BlogErrorKind::ConflictingField {
+                field,
+                front_matter: front,
+                legacy: legacy.clone(),
+            },
+        )
+        .at_line(error::line_of_key(markdown, field))),
+        (Some(front), _) => Ok(front),
+        (None, Some(legacy)) => Ok(legacy.clone()),
+        (None, None) => Err(BlogError::new(post, BlogErrorKind::MissingField { field })),
+    }
+}
+
+fn read_legacy_entries(errors: &mut BlogErrors) -> HashMap<String, LegacyBlogEntry> {
+    let info_path = Path::new(INFO_JSON);
+    if !info_path.exists() {
+        return HashMap::new();
+    }
+
+    match errors.check(
+        fs::read_to_string(info_path)
+            .map_err(|err| BlogError::new(info_path, BlogErrorKind::Io(err))),
+    ) {
+        Some(entries) => parse_legacy_entries(&entries, errors),
+        None => HashMap::new(),
+    }
+}
+
+/// Reads the entries of `blogs/info.json`, keyed by date, with the line each
+/// one is on.
+fn parse_legacy_entries(
+    entries: &str,
+    errors: &mut BlogErrors,
+) -> HashMap<String, LegacyBlogEntry> {
+    let info_path = Path::new(INFO_JSON);
+    let Some(deserialized) = errors.check(
+        serde_json::from_str::<Vec<LegacyBlogEntry>>(entries).map_err(|err| {
+            BlogError::new(
+                info_path,
+                BlogErrorKind::InvalidIndex {
+                    message: err.to_string(),
+                },
+            )
+            .at_line(Some(err.line()))
+        }),
+    ) else {
+        return HashMap::new();
+    };
+
+    let mut legacy_entries: HashMap<String, LegacyBlogEntry> = HashMap::new();
+    // Entries are in file order, so each one is after the previous one, which
+    // tells apart entries with the same date
+    let mut search_from = 0;
+    for mut entry in deserialized {
+        let date = format!("{:?}", entry.blog_date);
+        if let Some(offset) = entries[search_from..].find(&date) {
+            let offset = search_from + offset;
+            entry.line = Some(error::line_of_offset(entries, offset));
+            search_from = offset + date.len();
+        }
+        if let Some(first) = legacy_entries.get(&entry.blog_date) {
+            let first = match first.line {
+                Some(line) => format!("{INFO_JSON}:{line}"),
+                None => INFO_JSON.to_string(),
+            };
+            errors.push(
+                BlogError::new(
+                    info_path,
+                    BlogErrorKind::DuplicatePost {
+                        post: entry.blog_date.clone(),
+                        first,
+                    },
+                )
+                .at_line(entry.line),
+            );
+            continue;
+        }
+        legacy_entries.insert(entry.blog_date.clone(), entry);
+    }
+    legacy_entries
+}
+
+/// Splits the name of a post directory, `YYYY-MM-DD` optionally followed by
+/// `-<slug>`, into its date and slug.
+fn split_post_dir(dir_name: &str) -> Option<(&str, Option<&str>)> {
+    let (date, rest) = dir_name.split_at_checked(10)?;
+    let is_date = date.bytes().enumerate().all(|(index, byte)| match index {
+        4 | 7 => byte == b'-',
+        _ => byte.is_ascii_digit(),
+    });
+    if !is_date {
+        return None;
+    }
+    match rest.strip_prefix('-') {
+        _ if rest.is_empty() => Some((date, None)),
+        Some(slug) if !slug.is_empty() => Some((date, Some(slug))),
+        _ => None,
+    }
+}
+
+fn read_post(
+    post_dir: &Path,
+    legacy: Option<&LegacyBlogEntry>,
+    crate_dir: &Path,
+) -> Result<BlogPost, Vec<BlogError>> {
+    let dir_name = post_dir.file_name().unwrap().to_string_lossy().to_string();
+    let post = post_dir.join("index.md");
+    let markdown = fs::read_to_string(&post)
+        .map_err(|err| vec![BlogError::new(&post, BlogErrorKind::Io(err))])?;
+    parse_post(&post, &dir_name, &markdown, legacy, crate_dir)
+}
+
+/// Reads the post at `post`, in the directory `dir_name`, whose index has
+/// `markdown`.
+fn parse_post(
+    post: &Path,
+    dir_name: &str,
+    markdown: &str,
+    legacy: Option<&LegacyBlogEntry>,
+    crate_dir: &Path,
+) -> Result<BlogPost, Vec<BlogError>> {
+    let Some((dir_date, dir_slug)) = split_post_dir(dir_name) else {
+        return Err(vec![BlogError::new(
+            post,
+            BlogErrorKind::InvalidPostDirectory {
+                directory: dir_name.to_string(),
+            },
+        )]);
+    };
+    let post = post.to_path_buf();
+    let (front_matter, body) = split_front_matter(&post, markdown).map_err(|err| vec![err])?;
+    let front_matter = front_matter.unwrap_or_default();
+    let legacy_field = |field: fn(&LegacyBlogEntry) -> &String| legacy.map(field);
+
+    let mut errors = vec![];
+    let body_line = markdown.lines().count() - body.lines().count() + 1;
+    errors.extend(check_demos(&post, markdown, crate_dir, &front_matter.demos));
+    let body =
+        shortcodes::extract_shortcodes(crate_dir, &post, body_line, &body, &front_matter.demos)
+            .map_err(|shortcode_errors| errors.extend(shortcode_errors))
+            .ok();
+    let date = merge_field(
+        &post,
+        markdown,
+        "date",
+        front_matter.date,
+        legacy_field(|legacy| &legacy.blog_date),
+    );
+    let title = merge_field(
+        &post,
+        markdown,
+        "title",
+        front_matter.title,
+        legacy_field(|legacy| &legacy.title),
+    );
+    let description = merge_field(
+        &post,
+        markdown,
+        "description",
+        front_matter.description,
+        legacy_field(|legacy| &legacy.description),
+    );
+    let image_file = merge_field(
+        &post,
+        markdown,
+        "image",
+        front_matter.image,
+        legacy_field(|legacy| &legacy.image_file),
+    );
+
+    let blog_date = date.and_then(|date| {
+        let blog_date = parse_blog_date(&date).ok_or_else(|| {
+            BlogError::new(&post, BlogErrorKind::InvalidDate { date: date.clone() })
+                .at_line(error::line_of_key(markdown, "date"))
+        })?;
+        if blog_date.to_string() != dir_date {
+            return Err(BlogError::new(
+                &post,
+                BlogErrorKind::DateMismatch {
+                    date,
+                    directory: dir_name.to_string(),
+                },
+            )
+            .at_line(error::line_of_key(markdown, "date")));
+        }
+        Ok(blog_date)
+    });
+
+    let slug = match (front_matter.slug, dir_slug) {
+        (Some(slug), Some(dir_slug)) if slug != dir_slug => Err(BlogError::new(
+            &post,
+            BlogErrorKind::SlugMismatch {
+                slug,
+                directory: dir_name.to_string(),
+            },
+        )
+        .at_line(error::line_of_key(markdown, "slug"))),
+        (Some(slug), _) => Ok(slug),
+        (None, Some(dir_slug)) => Ok(dir_slug.to_string()),
+        (None, None) => Ok(title.as_deref().map(slugify).unwrap_or_default()),
+    }
+    .and_then(|slug| {
+        if is_valid_slug(&slug) {
+            Ok(slug)
+        } else {
+            Err(BlogError::new(&post, BlogErrorKind::InvalidSlug { slug })
+                .at_line(error::line_of_key(markdown, "slug")))
+        }
+    });
+
+    let image_file = image_file.and_then(|image| {
+        if crate_dir.join(&image).is_file() {
+            Ok(image)
+        } else {
+            Err(BlogError::new(&post, BlogErrorKind::MissingImage { image })
+                .at_line(error::line_of_key(markdown, "image")))
+        }
+    });
+
+    let blog_date = blog_date.map_err(|err| errors.push(err)).ok();
+    let slug = slug.map_err(|err| errors.push(err)).ok();
+    let title = title.map_err(|err| errors.push(err)).ok();
+    let description = description.map_err(|err| errors.push(err)).ok();
+    let image_file = image_file.map_err(|err| errors.push(err)).ok();
+
+    match (blog_date, slug, title, description, image_file, body) {
+        (
+            Some(blog_date),
+            Some(slug),
+            Some(title),
+            Some(description),
+            Some(image_file),
+            Some((body, shortcodes)),
+        ) if errors.is_empty() => Ok(BlogPost {
+            blog_date,
+            slug,
+            title,
+            description,
+            image_file,
+            tags: front_matter.tags,
+            draft: front_matter.draft,
+            path: post,
+            body,
+            shortcodes,
+            demos: front_matter.demos,
+        }),
+        _ => Err(errors),
+    }
+}
+
+fn discover_posts(crate_dir: &Path, errors: &mut BlogErrors) -> Vec<BlogPost> {
+    let legacy_entries = read_legacy_entries(errors);
+
+    let Some(read_dir) = errors.check(
+        fs::read_dir("blogs").map_err(|err| BlogError::new("blogs", BlogErrorKind::Io(err))),
+    ) else {
+        return vec![];
+    };
+    let mut post_dirs = read_dir
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_dir())
+        .collect::<Vec<PathBuf>>();
+    post_dirs.sort();
+
+    let mut legacy_dates = legacy_entries.keys().collect::<Vec<_>>();
+    legacy_dates.sort();
+    for date in legacy_dates {
+        let markdown = Path::new("blogs").join(date).join("index.md");
+        if !markdown.is_file() {
+            errors.push(
+                BlogError::new(INFO_JSON, BlogErrorKind::MissingMarkdown { markdown })
+                    .at_line(legacy_entries[date].line),
+            );
+        }
+    }
+
+    let mut posts: Vec<BlogPost> = vec![];
+    let mut seen_posts: HashMap<(BlogDate, String), String> = HashMap::new();
+    for post_dir in post_dirs {
+        if !post_dir.join("index.md").is_file() {
+            continue;
+        }
+        let dir_name = post_dir.file_name().unwrap().to_string_lossy().to_string();
+        match read_post(&post_dir, legacy_entries.get(&dir_name), crate_dir) {
+            Ok(post) => {
+                let index = post_dir.join("index.md");
+                let id = (post.blog_date, post.slug.clone());
+                if let Some(first) = seen_posts.get(&id) {
+                    errors.push(BlogError::new(
+                        &index,
+                        BlogErrorKind::DuplicatePost {
+                            post: format!("{}/{}", post.blog_date, post.slug),
+                            first: first.clone(),
+                        },
+                    ));
+                    continue;
+                }
+                seen_posts.insert(id, index.display().to_string());
+                posts.push(post);
+            }
+            Err(post_errors) => post_errors.into_iter().for_each(|err| errors.push(err)),
+        }
+    }
+    posts
+}
 
-    let mut file = std::io::BufWriter::new(fs::File::create(&dest_path).unwrap());
-
-    writeln!(file, "const BLOGS: [BlogEntry; {}] = [", deserialized.len()).unwrap();
-    for entry in &deserialized {
-        let date_fields = entry
-            .blog_date
-            .split('-')
-            .map(|x| x.parse::<u64>().unwrap())
-            .collect::<Vec<u64>>();
-        if date_fields.len() != 3 {
-            panic!("Invalid date format");
-        }
-        writeln!(file, "BlogEntry {{").unwrap();
-        writeln!(file, "blog_date: crate::sections::BlogDate {{").unwrap();
-        writeln!(file, "year: {},", date_fields[0]).unwrap();
-        writeln!(file, "month: {},", date_fields[1]).unwrap();
-        writeln!(file, "day: {},", date_fields[2]).unwrap();
-        writeln!(file, "}},").unwrap();
-        writeln!(file, "title: \"{}\",", entry.title).unwrap();
-        writeln!(file, "description: \"{}\",", entry.description).unwrap();
+fn blog_generator(
+    out_dir: &std::path::Path,
+    syntax_set: &SyntaxSet,
+    blog: &BlogPost,
+) -> Result<(String, Vec<headings::Heading>), BlogError> {
+    let dest_path = out_dir.join(format!("blogs/{}/{}", blog.blog_date, blog.slug));
+    let io_error = |err| BlogError::new(&dest_path, BlogErrorKind::Io(err));
+    if !dest_path.exists() {
+        fs::create_dir_all(&dest_path).map_err(io_error)?;
+    }
+    let dest_path_file = dest_path.join("index.html");
+
+    let render = |markdown: &str| {
+        let (html, unknown_languages) =
+            highlight::highlight_code_blocks(&blog.path, &markdown::to_html(markdown), syntax_set)?;
+        for language in unknown_languages {
+            println!(
+                "cargo::warning={}: no highlighting for {language:?} code blocks",
+                blog.path.display()
+            );
+        }
+        Ok(html)
+    };
+    let (html, toc) = headings::anchor_headings(&render(&blog.body)?);
+    let html =
+        shortcodes::insert_shortcodes(&blog.path, &html, &blog.shortcodes, &blog.route(), render)?;
+    fs::write(&dest_path_file, format!("{html}\n")).map_err(io_error)?;
+    Ok((html, toc))
+}
+
+/// Writes `feed.xml` (RSS) and `atom.xml` next to the other root assets.
+fn write_feeds(assets_dir: &Path, posts: &[BlogPost], htmls: &[String]) -> Result<(), BlogError> {
+    let cname_path = assets_dir.join("CNAME");
+    let cname = fs::read_to_string(&cname_path)
+        .map_err(|err| BlogError::new(&cname_path, BlogErrorKind::Io(err)))?;
+    let site_url = format!("https://{}", cname.trim());
+
+    let mut items = posts
+        .iter()
+        .zip(htmls)
+        .filter(|(post, _)| !post.draft)
+        .map(|(post, html)| feed::FeedItem {
+            title: &post.title,
+            description: &post.description,
+            url: format!("{site_url}{}", post.route()),
+            year: post.blog_date.year,
+            month: post.blog_date.month,
+            day: post.blog_date.day,
+            html,
+        })
+        .collect::<Vec<_>>();
+    items.sort_by_key(|item| std::cmp::Reverse((item.year, item.month, item.day)));
+
+    for (file_name, contents) in [
+        ("feed.xml", feed::rss(&site_url, &items)),
+        ("atom.xml", feed::atom(&site_url, &items)),
+    ] {
+        let path = assets_dir.join(file_name);
+        fs::write(&path, contents).map_err(|err| BlogError::new(&path, BlogErrorKind::Io(err)))?;
+    }
+    Ok(())
+}
+
+fn write_blog_index(
+    dest_path: &Path,
+    posts: &[BlogPost],
+    tocs: &[Vec<headings::Heading>],
+) -> std::io::Result<()> {
+    let mut file = std::io::BufWriter::new(fs::File::create(dest_path)?);
+
+    writeln!(file, "const BLOGS: [BlogEntry; {}] = [", posts.len())?;
+    for (entry, toc) in posts.iter().zip(tocs) {
+        writeln!(file, "BlogEntry {{")?;
+        writeln!(file, "blog_id: crate::sections::BlogId {{")?;
+        writeln!(file, "date: crate::sections::BlogDate {{")?;
+        writeln!(file, "year: {},", entry.blog_date.year)?;
+        writeln!(file, "month: {},", entry.blog_date.month)?;
+        writeln!(file, "day: {},", entry.blog_date.day)?;
+        writeln!(file, "}},")?;
+        writeln!(file, "slug: {:?},", entry.slug)?;
+        writeln!(file, "}},")?;
+        writeln!(file, "title: {:?},", entry.title)?;
+        writeln!(file, "description: {:?},", entry.description)?;
+        writeln!(file, "tags: &{:?},", entry.tags)?;
+        writeln!(file, "toc: &[")?;
+        for heading in toc {
+            writeln!(
+                file,
+                "TocEntry {{ level: {}, id: {:?}, title: {:?} }},",
+                heading.level, heading.id, heading.title
+            )?;
+        }
+        writeln!(file, "],")?;
+        writeln!(file, "demos: &[")?;
+        for demo in &entry.demos {
+            writeln!(file, "Demo {{")?;
+            writeln!(file, "module: {:?},", demo.module)?;
+            writeln!(file, "canvas_id: {:?},", demo.id)?;
+            writeln!(file, "scene: {:?},", demo.scene)?;
+            let (width, height) = demo.aspect_ratio().unwrap_or((1, 1));
+            writeln!(
+                file,
+                "aspect_ratio: AspectRatio {{ width: {width}, height: {height} }},"
+            )?;
+            writeln!(file, "max_resolution: {:?},", demo.max_resolution)?;
+            match &demo.fallback_image {
+                Some(image) => writeln!(
+                    file,
+                    "fallback_image: Some(asset!(\"{image}\", ImageAssetOptions::new().with_avif())),"
+                )?,
+                None => writeln!(file, "fallback_image: None,")?,
+            }
+            writeln!(file, "fallback_text: {:?},", demo.fallback_text)?;
+            writeln!(file, "}},")?;
+        }
+        writeln!(file, "],")?;
+        writeln!(file, "shortcodes: &[")?;
+        for placed in &entry.shortcodes {
+            writeln!(file, "{},", placed.shortcode.to_rust())?;
+        }
+        writeln!(file, "],")?;
         writeln!(
             file,
             "image_file_thumbnail: asset!(\"{}\",",
             entry.image_file
-        )
-        .unwrap();
+        )?;
         writeln!(
             file,
             "ImageAssetOptions::new().with_size(ImageSize::Manual {{width: 384, height: 384}}).with_avif()),",
-        )
-        .unwrap();
-        writeln!(file, "image_file_blog: asset!(\"{}\",", entry.image_file).unwrap();
-        writeln!(file, "ImageAssetOptions::new().with_avif()),",).unwrap();
-
-        let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
-        let crate_dir = Path::new(&crate_dir);
-        let html_path_file = crate_dir.join(format!("assets/blogs/{}/index.html", entry.blog_date));
-        println!("html_path_file: {:?}", html_path_file);
-        println!("crate_dir: {:?}", crate_dir);
-        let relative_html_path = html_path_file
-            .strip_prefix(crate_dir)
-            .unwrap()
-            .to_str()
-            .unwrap();
-        writeln!(file, "html: asset!(\"{}\"),", relative_html_path).unwrap();
-        writeln!(file, "}},").unwrap();
-    }
-    writeln!(file, "];").unwrap();
-
-    for entry in &deserialized {
-        blog_generator(assets_dir.as_path(), entry);
+        )?;
+        writeln!(file, "image_file_blog: asset!(\"{}\",", entry.image_file)?;
+        writeln!(file, "ImageAssetOptions::new().with_avif()),",)?;
+        writeln!(
+            file,
+            "html: asset!(\"assets/blogs/{}/{}/index.html\"),",
+            entry.blog_date, entry.slug
+        )?;
+        writeln!(file, "}},")?;
+    }
+    writeln!(file, "];")?;
+    file.flush()
+}
+
+/// Drafts are only built when this is set to something other than `0`, to
+/// preview them. Published builds can't pick them up by accident.
+const DRAFTS_VARIABLE: &str = "BLOG_DRAFTS";
+
+fn include_drafts(value: Option<&std::ffi::OsStr>) -> bool {
+    value.is_some_and(|value| !value.is_empty() && value != "0")
+}
+
+fn main() {
+    println!("cargo::rerun-if-changed=blogs");
+    println!("cargo::rerun-if-changed=build.rs");
+    println!("cargo::rerun-if-changed=build");
+    println!("cargo::rerun-if-changed=assets/CNAME");
+    println!("cargo::rerun-if-env-changed={DRAFTS_VARIABLE}");
+
+    let out_dir = env::var_os("OUT_DIR").unwrap();
+    let out_dir = Path::new(&out_dir);
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    let crate_dir = Path::new(&crate_dir);
+    let assets_dir = crate_dir.join("assets");
+
+    let mut errors = BlogErrors::default();
+
+    let include_drafts = include_drafts(env::var_os(DRAFTS_VARIABLE).as_deref());
+    let posts = discover_posts(crate_dir, &mut errors)
+        .into_iter()
+        .filter(|post| include_drafts || !post.draft)
+        .collect::<Vec<_>>();
+
+    let syntax_set = SyntaxSet::load_defaults_newlines();
+    let (htmls, tocs): (Vec<_>, Vec<_>) = posts
+        .iter()
+        .map(|entry| {
+            errors
+                .check(blog_generator(assets_dir.as_path(), &syntax_set, entry))
+                .unwrap_or_default()
+        })
+        .unzip();
+
+    if errors.is_empty() {
+        errors.check(write_feeds(&assets_dir, &posts, &htmls));
+    }
+
+    if !errors.is_empty() {
+        eprintln!("{errors}");
+        std::process::exit(1);
+    }
+
+    let dest_path = out_dir.join("current_blogs.rs");
+    write_blog_index(&dest_path, &posts, &tocs).expect("failed to write current_blogs.rs");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POST: &str = "blogs/2024-11-23/index.md";
+    const YAML_POST: &str = "---
+title: A post
+description: About things
+image: ./raw_assets/img/top.jpg
+date: 2024-11-23
+tags: [rust]
+---
+
+# Body
+
+date: not front matter
+";
+
+    fn crate_dir() -> &'static Path {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+    }
+
+    fn legacy(date: &str, title: &str) -> LegacyBlogEntry {
+        LegacyBlogEntry {
+            blog_date: date.to_string(),
+            title: title.to_string(),
+            description: "About things".to_string(),
+            image_file: "./raw_assets/img/top.jpg".to_string(),
+            line: None,
+        }
+    }
+
+    fn parse(
+        dir_name: &str,
+        markdown: &str,
+        legacy: Option<&LegacyBlogEntry>,
+    ) -> Result<BlogPost, Vec<BlogError>> {
+        parse_post(Path::new(POST), dir_name, markdown, legacy, crate_dir())
+    }
+
+    /// The errors of `result`, as they're printed.
+    fn messages<T: std::fmt::Debug>(result: Result<T, Vec<BlogError>>) -> Vec<String> {
+        result
+            .unwrap_err()
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn yaml_and_toml_front_matter() {
+        let (front_matter, body) = split_front_matter(Path::new(POST), YAML_POST).unwrap();
+        let front_matter = front_matter.unwrap();
+        assert_eq!(front_matter.title.as_deref(), Some("A post"));
+        assert_eq!(front_matter.tags, ["rust"]);
+        assert!(body.starts_with("\n# Body"));
+
+        let toml = "+++\ntitle = \"A post\"\ndraft = true\n+++\nBody";
+        let (front_matter, body) = split_front_matter(Path::new(POST), toml).unwrap();
+        let front_matter = front_matter.unwrap();
+        assert_eq!(front_matter.title.as_deref(), Some("A post"));
+        assert!(front_matter.draft);
+        assert_eq!(body, "Body");
+    }
+
+    #[test]
+    fn markdown_without_front_matter() {
+        let (front_matter, body) = split_front_matter(Path::new(POST), "# Title\n").unwrap();
+        assert!(front_matter.is_none());
+        assert_eq!(body, "# Title\n");
+    }
+
+    #[test]
+    fn broken_front_matter() {
+        let unclosed = split_front_matter(Path::new(POST), "---\ntitle: A\n").unwrap_err();
+        assert_eq!(
+            unclosed.to_string(),
+            format!("{POST}:1: front matter opened with `---` is never closed")
+        );
+
+        let unknown =
+            split_front_matter(Path::new(POST), "---\ntitle: A\nauthor: B\n---\n").unwrap_err();
+        assert!(matches!(
+            unknown.kind,
+            BlogErrorKind::InvalidFrontMatter { format: "YAML", .. }
+        ));
+        assert_eq!(unknown.line, Some(3));
+
+        let invalid = split_front_matter(Path::new(POST), "+++\ntitle = \"A\"\ndraft = 3\n+++\n")
+            .unwrap_err();
+        assert!(matches!(
+            invalid.kind,
+            BlogErrorKind::InvalidFrontMatter { format: "TOML", .. }
+        ));
+        assert_eq!(invalid.line, Some(3));
+    }
+
+    #[test]
+    fn fields_merge_with_the_legacy_index() {
+        let legacy_title = "A post".to_string();
+        let merge = |front: Option<&str>, legacy| {
+            merge_field(
+                Path::new(POST),
+                YAML_POST,
+                "title",
+                front.map(String::from),
+                legacy,
+            )
+        };
+        assert_eq!(
+            merge(Some("A post"), Some(&legacy_title)).unwrap(),
+            "A post"
+        );
+        assert_eq!(merge(None, Some(&legacy_title)).unwrap(), "A post");
+        assert_eq!(merge(Some("New"), None).unwrap(), "New");
+
+        let conflict = merge(Some("New"), Some(&legacy_title)).unwrap_err();
+        assert_eq!(
+            conflict.to_string(),
+            format!("{POST}:2: `title` is \"New\" in the front matter but \"A post\" in blogs/info.json")
+        );
+        let missing = merge(None, None).unwrap_err();
+        assert_eq!(
+            missing.to_string(),
+            format!("{POST}: missing `title` (add it to the front matter)")
+        );
+    }
+
+    #[test]
+    fn legacy_entries_are_keyed_by_date() {
+        let json = r#"[
+  {
+    "blog_date": "2024-11-23",
+    "title": "A post",
+    "description": "About 2024-11-24",
+    "image_file": "./raw_assets/img/top.jpg"
+  },
+  {
+    "blog_date": "2024-11-24",
+    "title": "Another post",
+    "description": "About things",
+    "image_file": "./raw_assets/img/top.jpg"
+  }
+]"#;
+        let mut errors = BlogErrors::default();
+        let entries = parse_legacy_entries(json, &mut errors);
+        assert!(errors.is_empty(), "{errors}");
+        assert_eq!(entries["2024-11-23"].title, "A post");
+        assert_eq!(entries["2024-11-23"].line, Some(3));
+        // Not the date in the description of the first entry
+        assert_eq!(entries["2024-11-24"].line, Some(9));
+    }
+
+    #[test]
+    fn duplicate_legacy_entries_point_at_both_lines() {
+        let entry =
+            r#"{"blog_date": "2024-11-23", "title": "A", "description": "B", "image_file": "C"}"#;
+        let json = format!("[\n{entry},\n{entry},\n{entry}\n]");
+        let mut errors = BlogErrors::default();
+        let entries = parse_legacy_entries(&json, &mut errors);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(
+            errors.to_string(),
+            format!(
+                "found 2 problem(s) in the blog:\n  \
+                 {INFO_JSON}:3: 2024-11-23 is already used by {INFO_JSON}:2\n  \
+                 {INFO_JSON}:4: 2024-11-23 is already used by {INFO_JSON}:2\n"
+            )
+        );
+    }
+
+    #[test]
+    fn invalid_legacy_index() {
+        let mut errors = BlogErrors::default();
+        let entries = parse_legacy_entries("[\n  {\"blog_date\": 1}\n]", &mut errors);
+        assert!(entries.is_empty());
+        assert!(
+            errors.to_string().contains(&format!("{INFO_JSON}:2: ")),
+            "{errors}"
+        );
+    }
+
+    #[test]
+    fn post_directory_names() {
+        assert_eq!(split_post_dir("2024-11-23"), Some(("2024-11-23", None)));
+        assert_eq!(
+            split_post_dir("2024-11-23-a-post"),
+            Some(("2024-11-23", Some("a-post")))
+        );
+        for invalid in [
+            "2024-11-23foo",
+            "2024-11-23-",
+            "2024-1-023",
+            "24-11-2023",
+            "2024-11",
+            "drafts",
+        ] {
+            assert_eq!(split_post_dir(invalid), None, "{invalid}");
+        }
+    }
+
+    #[test]
+    fn drafts_are_opt_in() {
+        assert!(!include_drafts(None));
+        assert!(!include_drafts(Some("".as_ref())));
+        assert!(!include_drafts(Some("0".as_ref())));
+        assert!(include_drafts(Some("1".as_ref())));
+    }
+
+    #[test]
+    fn invalid_post_directories_are_errors() {
+        assert_eq!(
+            messages(parse("2024-11-23foo", YAML_POST, None)),
+            [format!(
+                "{POST}: directory \"2024-11-23foo\" should be named YYYY-MM-DD or YYYY-MM-DD-<slug>"
+            )]
+        );
+    }
+
+    #[test]
+    fn titles_make_slugs() {
+        assert_eq!(slugify("Hello, World!"), "hello-world");
+        assert_eq!(slugify("  WGPU 23: what's new  "), "wgpu-23-what-s-new");
+        assert_eq!(slugify("¡Olé!"), "ol");
+        assert_eq!(slugify("???"), "");
+    }
+
+    #[test]
+    fn slugs_come_from_the_front_matter_directory_or_title() {
+        let with_slug = "---\ntitle: Hello, World!\ndescription: A\nimage: ./raw_assets/img/top.jpg\ndate: 2024-11-23\nslug: hi\n---\n";
+        let without_slug = with_slug.replace("slug: hi\n", "");
+        let slug = |dir_name, markdown: &str| parse(dir_name, markdown, None).map(|post| post.slug);
+
+        assert_eq!(slug("2024-11-23", with_slug).unwrap(), "hi");
+        assert_eq!(slug("2024-11-23-hi", with_slug).unwrap(), "hi");
+        assert_eq!(slug("2024-11-23-hello", &without_slug).unwrap(), "hello");
+        assert_eq!(slug("2024-11-23", &without_slug).unwrap(), "hello-world");
+
+        assert_eq!(
+            messages(slug("2024-11-23-hello", with_slug)),
+            [format!(
+                "{POST}:6: slug \"hi\" doesn't match its directory name \"2024-11-23-hello\""
+            )]
+        );
+        assert_eq!(
+            messages(slug("2024-11-23", &with_slug.replace("hi", "Hi There"))),
+            [format!(
+                "{POST}:6: invalid slug \"Hi There\", only lowercase letters, digits and `-` are allowed"
+            )]
+        );
+    }
+
+    #[test]
+    fn invalid_dates_are_errors() {
+        let legacy = legacy("2024-13-45", "A post");
+        let errors = messages(parse("2024-13-45", "# Body\n", Some(&legacy)));
+        assert_eq!(errors.len(), 1, "{errors:?}");
+        assert!(errors[0].starts_with(&format!("{POST}: ")), "{errors:?}");
+        assert!(errors[0].contains("2024-13-45"), "{errors:?}");
+    }
+
+    #[test]
+    fn posts_merge_front_matter_and_legacy_entries() {
+        let post = parse("2024-11-23", YAML_POST, None).unwrap();
+        assert_eq!(post.blog_date.to_string(), "2024-11-23");
+        assert_eq!(post.title, "A post");
+        assert_eq!(post.tags, ["rust"]);
+
+        // Only the legacy index has the metadata
+        let legacy = legacy("2024-11-23", "Legacy post");
+        let post = parse("2024-11-23", "# Body\n", Some(&legacy)).unwrap();
+        assert_eq!(post.title, "Legacy post");
+        assert_eq!(post.description, "About things");
+    }
+
+    #[test]
+    fn every_problem_of_a_post_is_reported() {
+        let markdown = "---
+title: A post
+date: 2024-11-24
+image: ./missing.png
+---
+";
+        assert_eq!(
+            messages(parse("2024-11-23", markdown, None)),
+            [
+                format!(
+                    "{POST}:3: date \"2024-11-24\" doesn't match its directory name \"2024-11-23\""
+                ),
+                format!("{POST}: missing `description` (add it to the front matter)"),
+                format!("{POST}:4: image \"./missing.png\" doesn't exist"),
+            ]
+        );
     }
 }
@@ -0,0 +1,253 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Everything that can be wrong with a single post.
+#[derive(Debug)]
+pub enum BlogErrorKind {
+    Io(std::io::Error),
+    UnclosedFrontMatter {
+        delimiter: &'static str,
+    },
+    InvalidFrontMatter {
+        format: &'static str,
+        message: String,
+    },
+    InvalidIndex {
+        message: String,
+    },
+    InvalidPostDirectory {
+        directory: String,
+    },
+    MissingField {
+        field: &'static str,
+    },
+    ConflictingField {
+        field: &'static str,
+        front_matter: String,
+        legacy: String,
+    },
+    InvalidDate {
+        date: String,
+    },
+    DateMismatch {
+        date: String,
+        directory: String,
+    },
+    MissingMarkdown {
+        markdown: PathBuf,
+    },
+    MissingImage {
+        image: String,
+    },
+    InvalidSlug {
+        slug: String,
+    },
+    SlugMismatch {
+        slug: String,
+        directory: String,
+    },
+    DuplicatePost {
+        post: String,
+        first: String,
+    },
+    InvalidDemo {
+        id: String,
+        message: String,
+    },
+    UnknownShortcode {
+        name: String,
+    },
+    InvalidShortcode {
+        name: String,
+        message: String,
+    },
+    InvalidCodeBlock {
+        message: String,
+    },
+}
+
+impl fmt::Display for BlogErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BlogErrorKind::Io(err) => write!(f, "{err}"),
+            BlogErrorKind::UnclosedFrontMatter { delimiter } => {
+                write!(f, "front matter opened with `{delimiter}` is never closed")
+            }
+            BlogErrorKind::InvalidFrontMatter { format, message } => {
+                write!(f, "invalid {format} front matter: {message}")
+            }
+            BlogErrorKind::InvalidIndex { message } => write!(f, "invalid index: {message}"),
+            BlogErrorKind::InvalidPostDirectory { directory } => write!(
+                f,
+                "directory {directory:?} should be named YYYY-MM-DD or YYYY-MM-DD-<slug>"
+            ),
+            BlogErrorKind::MissingField { field } => {
+                write!(f, "missing `{field}` (add it to the front matter)")
+            }
+            BlogErrorKind::ConflictingField {
+                field,
+                front_matter,
+                legacy,
+            } => write!(
+                f,
+                "`{field}` is {front_matter:?} in the front matter but {legacy:?} in blogs/info.json"
+            ),
+            BlogErrorKind::InvalidDate { date } => {
+                write!(f, "invalid date {date:?}, expected YYYY-MM-DD")
+            }
+            BlogErrorKind::DateMismatch { date, directory } => write!(
+                f,
+                "date {date:?} doesn't match its directory name {directory:?}"
+            ),
+            BlogErrorKind::MissingMarkdown { markdown } => {
+                write!(f, "lists a post but {} doesn't exist", markdown.display())
+            }
+            BlogErrorKind::MissingImage { image } => write!(f, "image {image:?} doesn't exist"),
+            BlogErrorKind::InvalidSlug { slug } => write!(
+                f,
+                "invalid slug {slug:?}, only lowercase letters, digits and `-` are allowed"
+            ),
+            BlogErrorKind::SlugMismatch { slug, directory } => write!(
+                f,
+                "slug {slug:?} doesn't match its directory name {directory:?}"
+            ),
+            BlogErrorKind::DuplicatePost { post, first } => {
+                write!(f, "{post} is already used by {first}")
+            }
+            BlogErrorKind::InvalidDemo { id, message } => {
+                write!(f, "invalid demo {id:?}: {message}")
+            }
+            BlogErrorKind::UnknownShortcode { name } => write!(
+                f,
+                "unknown shortcode `{name}`, expected wasm-demo, youtube, figure or note"
+            ),
+            BlogErrorKind::InvalidShortcode { name, message } => {
+                write!(f, "invalid `{name}` shortcode: {message}")
+            }
+            BlogErrorKind::InvalidCodeBlock { message } => {
+                write!(f, "invalid code block: {message}")
+            }
+        }
+    }
+}
+
+/// A problem found while processing the blog, pointing at the file (and line,
+/// when known) that caused it.
+#[derive(Debug)]
+pub struct BlogError {
+    pub path: PathBuf,
+    pub line: Option<usize>,
+    pub kind: BlogErrorKind,
+}
+
+impl BlogError {
+    pub fn new(path: impl AsRef<Path>, kind: BlogErrorKind) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            line: None,
+            kind,
+        }
+    }
+
+    pub fn at_line(mut self, line: Option<usize>) -> Self {
+        self.line = line;
+        self
+    }
+}
+
+impl fmt::Display for BlogError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}", self.path.display(), line, self.kind),
+            None => write!(f, "{}: {}", self.path.display(), self.kind),
+        }
+    }
+}
+
+/// Collects every problem across all posts so a broken build reports all of
+/// them at once instead of stopping at the first one.
+#[derive(Debug, Default)]
+pub struct BlogErrors(Vec<BlogError>);
+
+impl BlogErrors {
+    pub fn push(&mut self, error: BlogError) {
+        self.0.push(error);
+    }
+
+    /// Records the error of `result`, if any, and hands back its value.
+    pub fn check<T>(&mut self, result: Result<T, BlogError>) -> Option<T> {
+        result.map_err(|err| self.push(err)).ok()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl fmt::Display for BlogErrors {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "found {} problem(s) in the blog:", self.0.len())?;
+        for error in &self.0 {
+            writeln!(f, "  {error}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the 1-based line of `text` that contains the byte `offset`.
+pub fn line_of_offset(text: &str, offset: usize) -> usize {
+    text[..offset.min(text.len())].matches('\n').count() + 1
+}
+
+/// Returns the 1-based line of `markdown` where the front-matter `key` is
+/// defined, for either the YAML (`key:`) or the TOML (`key =`) syntax. The body
+/// isn't searched, as it may have lines that look the same.
+pub fn line_of_key(markdown: &str, key: &str) -> Option<usize> {
+    let mut lines = markdown.lines();
+    let delimiter = lines.next()?.trim_end();
+    if delimiter != "---" && delimiter != "+++" {
+        return None;
+    }
+    lines
+        .take_while(|line| line.trim_end() != delimiter)
+        .position(|line| {
+            line.strip_prefix(key)
+                .map(|rest| rest.trim_start().starts_with([':', '=']))
+                .unwrap_or(false)
+        })
+        // Past the opening delimiter
+        .map(|index| index + 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_of_offsets() {
+        let text = "one\ntwo\nthree";
+        assert_eq!(line_of_offset(text, 0), 1);
+        assert_eq!(line_of_offset(text, 4), 2);
+        assert_eq!(line_of_offset(text, 100), 3);
+    }
+
+    #[test]
+    fn keys_are_found_in_yaml_and_toml() {
+        assert_eq!(
+            line_of_key("---\ntitle: A\ndate: 2024-11-23\n---\n", "date"),
+            Some(3)
+        );
+        assert_eq!(
+            line_of_key("+++\ntitle = \"A\"\ndate = \"2024-11-23\"\n+++\n", "date"),
+            Some(3)
+        );
+        assert_eq!(line_of_key("---\ndates: []\n---\n", "date"), None);
+    }
+
+    #[test]
+    fn keys_in_the_body_are_ignored() {
+        let markdown = "---\ntitle: A\n---\n\ndate: not front matter\n";
+        assert_eq!(line_of_key(markdown, "date"), None);
+        assert_eq!(line_of_key("date: no front matter\n", "date"), None);
+    }
+}
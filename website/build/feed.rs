@@ -0,0 +1,291 @@
+use std::fmt::Write;
+
+/// What the feeds need to know about a post, with its body already rendered.
+pub struct FeedItem<'a> {
+    pub title: &'a str,
+    pub description: &'a str,
+    pub url: String,
+    pub year: u64,
+    pub month: u8,
+    pub day: u8,
+    pub html: &'a str,
+}
+
+const FEED_TITLE: &str = "Pol's blog";
+const FEED_DESCRIPTION: &str = "Some of my ramblings";
+const FEED_AUTHOR: &str = "Pol Marcet Sardà";
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Wraps `text` in CDATA sections, splitting any `]]>` it contains.
+fn cdata(text: &str) -> String {
+    format!("<![CDATA[{}]]>", text.replace("]]>", "]]]]><![CDATA[>"))
+}
+
+/// Feed readers don't know where the post lives, so site-relative links in
+/// the body have to be made absolute. Protocol-relative ones (`//host/...`)
+/// already name their host and are left alone.
+fn absolute_links(html: &str, site_url: &str) -> String {
+    let prefixes: Vec<String> = ["src=", "href="]
+        .iter()
+        .flat_map(|attribute| ['\'', '"'].map(|quote| format!("{attribute}{quote}/")))
+        .collect();
+    let mut absolute = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some((start, prefix)) = prefixes
+        .iter()
+        .filter_map(|prefix| Some((rest.find(prefix.as_str())?, prefix)))
+        .min_by_key(|(start, _)| *start)
+    {
+        let end = start + prefix.len();
+        absolute.push_str(&rest[..end - 1]);
+        if !rest[end..].starts_with('/') {
+            absolute.push_str(site_url);
+        }
+        absolute.push('/');
+        rest = &rest[end..];
+    }
+    absolute.push_str(rest);
+    absolute
+}
+
+/// Day of the week for a date in the proleptic Gregorian calendar, using
+/// Sakamoto's method.
+fn weekday(year: u64, month: u8, day: u8) -> &'static str {
+    const OFFSETS: [u64; 12] = [0, 3, 2, 5, 0, 3, 5, 1, 4, 6, 2, 4];
+    const NAMES: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+    let year = if month < 3 { year - 1 } else { year };
+    let index =
+        (year + year / 4 - year / 100 + year / 400 + OFFSETS[month as usize - 1] + day as u64) % 7;
+    NAMES[index as usize]
+}
+
+fn rfc822_date(item: &FeedItem) -> String {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    format!(
+        "{}, {:02} {} {} 00:00:00 +0000",
+        weekday(item.year, item.month, item.day),
+        item.day,
+        MONTHS[item.month as usize - 1],
+        item.year
+    )
+}
+
+fn rfc3339_date(item: &FeedItem) -> String {
+    format!(
+        "{:04}-{:02}-{:02}T00:00:00Z",
+        item.year, item.month, item.day
+    )
+}
+
+/// Renders an RSS 2.0 feed. `items` are expected newest first.
+pub fn rss(site_url: &str, items: &[FeedItem]) -> String {
+    let mut xml = String::new();
+    writeln!(xml, r#"<?xml version="1.0" encoding="UTF-8"?>"#).unwrap();
+    writeln!(
+        xml,
+        r#"<rss version="2.0" xmlns:atom="http://www.w3.org/2005/Atom" xmlns:content="http://purl.org/rss/1.0/modules/content/">"#
+    )
+    .unwrap();
+    writeln!(xml, "<channel>").unwrap();
+    writeln!(xml, "<title>{}</title>", escape_xml(FEED_TITLE)).unwrap();
+    writeln!(xml, "<link>{site_url}/blog.html</link>").unwrap();
+    writeln!(
+        xml,
+        "<description>{}</description>",
+        escape_xml(FEED_DESCRIPTION)
+    )
+    .unwrap();
+    writeln!(
+        xml,
+        r#"<atom:link href="{site_url}/feed.xml" rel="self" type="application/rss+xml"/>"#
+    )
+    .unwrap();
+    if let Some(newest) = items.first() {
+        writeln!(
+            xml,
+            "<lastBuildDate>{}</lastBuildDate>",
+            rfc822_date(newest)
+        )
+        .unwrap();
+    }
+    for item in items {
+        writeln!(xml, "<item>").unwrap();
+        writeln!(xml, "<title>{}</title>", escape_xml(item.title)).unwrap();
+        writeln!(xml, "<link>{}</link>", escape_xml(&item.url)).unwrap();
+        writeln!(
+            xml,
+            r#"<guid isPermaLink="true">{}</guid>"#,
+            escape_xml(&item.url)
+        )
+        .unwrap();
+        writeln!(xml, "<pubDate>{}</pubDate>", rfc822_date(item)).unwrap();
+        writeln!(
+            xml,
+            "<description>{}</description>",
+            escape_xml(item.description)
+        )
+        .unwrap();
+        writeln!(
+            xml,
+            "<content:encoded>{}</content:encoded>",
+            cdata(&absolute_links(item.html, site_url))
+        )
+        .unwrap();
+        writeln!(xml, "</item>").unwrap();
+    }
+    writeln!(xml, "</channel>").unwrap();
+    writeln!(xml, "</rss>").unwrap();
+    xml
+}
+
+/// Renders an Atom feed. `items` are expected newest first.
+pub fn atom(site_url: &str, items: &[FeedItem]) -> String {
+    let mut xml = String::new();
+    writeln!(xml, r#"<?xml version="1.0" encoding="UTF-8"?>"#).unwrap();
+    writeln!(xml, r#"<feed xmlns="http://www.w3.org/2005/Atom">"#).unwrap();
+    writeln!(xml, "<title>{}</title>", escape_xml(FEED_TITLE)).unwrap();
+    writeln!(xml, "<subtitle>{}</subtitle>", escape_xml(FEED_DESCRIPTION)).unwrap();
+    writeln!(xml, "<id>{site_url}/</id>").unwrap();
+    writeln!(xml, r#"<link href="{site_url}/blog.html"/>"#).unwrap();
+    writeln!(xml, r#"<link href="{site_url}/atom.xml" rel="self"/>"#).unwrap();
+    writeln!(
+        xml,
+        "<author><name>{}</name></author>",
+        escape_xml(FEED_AUTHOR)
+    )
+    .unwrap();
+    let updated = items
+        .first()
+        .map(rfc3339_date)
+        .unwrap_or_else(|| "1970-01-01T00:00:00Z".to_string());
+    writeln!(xml, "<updated>{updated}</updated>").unwrap();
+    for item in items {
+        writeln!(xml, "<entry>").unwrap();
+        writeln!(xml, "<title>{}</title>", escape_xml(item.title)).unwrap();
+        writeln!(xml, r#"<link href="{}"/>"#, escape_xml(&item.url)).unwrap();
+        writeln!(xml, "<id>{}</id>", escape_xml(&item.url)).unwrap();
+        writeln!(xml, "<published>{}</published>", rfc3339_date(item)).unwrap();
+        writeln!(xml, "<updated>{}</updated>", rfc3339_date(item)).unwrap();
+        writeln!(xml, "<summary>{}</summary>", escape_xml(item.description)).unwrap();
+        writeln!(
+            xml,
+            r#"<content type="html">{}</content>"#,
+            escape_xml(&absolute_links(item.html, site_url))
+        )
+        .unwrap();
+        writeln!(xml, "</entry>").unwrap();
+    }
+    writeln!(xml, "</feed>").unwrap();
+    xml
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SITE_URL: &str = "https://example.com";
+
+    fn item(html: &str) -> FeedItem<'_> {
+        FeedItem {
+            title: "Fish & <chips>",
+            description: "\"Quoted\" 'text'",
+            url: format!("{SITE_URL}/blog/2024/11/23/fish.html"),
+            year: 2024,
+            month: 11,
+            day: 23,
+            html,
+        }
+    }
+
+    #[test]
+    fn xml_is_escaped() {
+        assert_eq!(
+            escape_xml(r#"<a href="x">Fish & 'chips'</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;Fish &amp; &apos;chips&apos;&lt;/a&gt;"
+        );
+        assert_eq!(escape_xml("&amp;"), "&amp;amp;");
+    }
+
+    #[test]
+    fn cdata_splits_its_terminator() {
+        assert_eq!(cdata("<p>x</p>"), "<![CDATA[<p>x</p>]]>");
+        assert_eq!(cdata("a]]>b"), "<![CDATA[a]]]]><![CDATA[>b]]>");
+    }
+
+    #[test]
+    fn site_relative_links_become_absolute() {
+        let html =
+            r#"<a href="/blog.html"><img src='/img/a.png'></a><a href="https://other.org/x">"#;
+        assert_eq!(
+            absolute_links(html, SITE_URL),
+            r#"<a href="https://example.com/blog.html"><img src='https://example.com/img/a.png'></a><a href="https://other.org/x">"#
+        );
+        // Relative to the post and fragment links are left alone
+        let html = r##"<a href="#intro"><img src="img.png">"##;
+        assert_eq!(absolute_links(html, SITE_URL), html);
+    }
+
+    #[test]
+    fn protocol_relative_links_are_left_alone() {
+        let html = r#"<img src="//cdn.example/x.png"><a href='//other.org/'><a href="/a//b">"#;
+        assert_eq!(
+            absolute_links(html, SITE_URL),
+            r#"<img src="//cdn.example/x.png"><a href='//other.org/'><a href="https://example.com/a//b">"#
+        );
+    }
+
+    #[test]
+    fn weekdays() {
+        assert_eq!(weekday(1970, 1, 1), "Thu");
+        assert_eq!(weekday(2000, 2, 29), "Tue");
+        assert_eq!(weekday(2000, 3, 1), "Wed");
+        assert_eq!(weekday(2024, 11, 23), "Sat");
+        assert_eq!(weekday(2024, 12, 31), "Tue");
+    }
+
+    #[test]
+    fn dates() {
+        let item = item("");
+        assert_eq!(rfc822_date(&item), "Sat, 23 Nov 2024 00:00:00 +0000");
+        assert_eq!(rfc3339_date(&item), "2024-11-23T00:00:00Z");
+    }
+
+    #[test]
+    fn rss_items() {
+        let xml = rss(SITE_URL, &[item(r#"<a href="/x">]]></a>"#)]);
+        assert!(
+            xml.contains("<title>Fish &amp; &lt;chips&gt;</title>"),
+            "{xml}"
+        );
+        assert!(xml.contains("<description>&quot;Quoted&quot; &apos;text&apos;</description>"));
+        assert!(xml.contains("<lastBuildDate>Sat, 23 Nov 2024 00:00:00 +0000</lastBuildDate>"));
+        assert!(xml.contains(
+            r#"<content:encoded><![CDATA[<a href="https://example.com/x">]]]]><![CDATA[></a>]]></content:encoded>"#
+        ));
+    }
+
+    #[test]
+    fn atom_entries() {
+        let xml = atom(SITE_URL, &[item(r#"<a href="/x">"#)]);
+        assert!(
+            xml.contains("<updated>2024-11-23T00:00:00Z</updated>"),
+            "{xml}"
+        );
+        assert!(xml.contains(
+            r#"<content type="html">&lt;a href=&quot;https://example.com/x&quot;&gt;</content>"#
+        ));
+
+        let empty = atom(SITE_URL, &[]);
+        assert!(empty.contains("<updated>1970-01-01T00:00:00Z</updated>"));
+        assert!(!empty.contains("<entry>"));
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::HashSet;
+
+/// A heading of a post, as listed in its table of contents.
+#[derive(Debug)]
+pub struct Heading {
+    pub level: u8,
+    pub id: String,
+    pub title: String,
+}
+
+fn strip_tags(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => (),
+        }
+    }
+    text
+}
+
+/// Finds the next `<hN id='...'>` tag `markdown` emits, returning its offset
+/// and level.
+fn find_heading(html: &str) -> Option<(usize, u8)> {
+    let mut offset = 0;
+    while let Some(start) = html[offset..].find("<h") {
+        let start = offset + start;
+        let level = html.as_bytes().get(start + 2).copied().unwrap_or_default();
+        if (b'1'..=b'6').contains(&level) && html[start + 3..].starts_with(" id='") {
+            return Some((start, level - b'0'));
+        }
+        offset = start + 2;
+    }
+    None
+}
+
+/// Replaces the ids `markdown` gives to headings with stable slugs, adds a
+/// permalink anchor to each of them and returns them in order.
+///
+/// Repeated titles get a `-1`, `-2`... suffix so every id stays unique.
+pub fn anchor_headings(html: &str) -> (String, Vec<Heading>) {
+    let mut anchored = String::with_capacity(html.len());
+    let mut headings = vec![];
+    let mut used_ids = HashSet::new();
+    let mut rest = html;
+
+    while let Some((start, level)) = find_heading(rest) {
+        anchored.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end_tag = format!("</h{level}>");
+        let (Some(open_end), Some(close)) = (rest.find("'>"), rest.find(&end_tag)) else {
+            break;
+        };
+        let contents = &rest[open_end + 2..close];
+        rest = &rest[close + end_tag.len()..];
+
+        let title = super::highlight::unescape(&strip_tags(contents))
+            .trim()
+            .to_string();
+        let mut slug = super::slugify(&title);
+        if slug.is_empty() {
+            slug = "section".to_string();
+        }
+        let mut id = slug.clone();
+        for repeat in 1.. {
+            if used_ids.insert(id.clone()) {
+                break;
+            }
+            id = format!("{slug}-{repeat}");
+        }
+
+        anchored.push_str(&format!(
+            "<h{level} id=\"{id}\">{contents}<a class=\"heading-anchor\" href=\"#{id}\" aria-label=\"Link to this section\">#</a>{end_tag}"
+        ));
+        headings.push(Heading { level, id, title });
+    }
+
+    anchored.push_str(rest);
+    (anchored, headings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(headings: &[Heading]) -> Vec<&str> {
+        headings.iter().map(|heading| heading.id.as_str()).collect()
+    }
+
+    #[test]
+    fn headings_get_anchors() {
+        let (html, headings) =
+            anchor_headings("<p>Intro</p>\n<h2 id='x'>Getting started</h2>\n<p>Text</p>");
+        assert_eq!(
+            html,
+            "<p>Intro</p>\n<h2 id=\"getting-started\">Getting started<a class=\"heading-anchor\" \
+             href=\"#getting-started\" aria-label=\"Link to this section\">#</a></h2>\n<p>Text</p>"
+        );
+        assert_eq!(headings.len(), 1);
+        assert_eq!(headings[0].level, 2);
+        assert_eq!(headings[0].title, "Getting started");
+    }
+
+    #[test]
+    fn titles_drop_tags_and_escapes() {
+        let (_, headings) =
+            anchor_headings("<h3 id='a'>Using <code>Vec&lt;T&gt;</code> &amp; friends</h3>");
+        assert_eq!(headings[0].title, "Using Vec<T> & friends");
+        assert_eq!(headings[0].id, "using-vec-t-friends");
+    }
+
+    #[test]
+    fn ids_stay_unique() {
+        let (html, headings) = anchor_headings(
+            "<h2 id='a'>Setup</h2><h3 id='b'>Setup</h3><h2 id='c'>Setup</h2><h2 id='d'>!!!</h2><h2 id='e'>?</h2>",
+        );
+        assert_eq!(
+            ids(&headings),
+            ["setup", "setup-1", "setup-2", "section", "section-1"]
+        );
+        assert_eq!(
+            headings
+                .iter()
+                .map(|heading| heading.level)
+                .collect::<Vec<_>>(),
+            [2, 3, 2, 2, 2]
+        );
+        assert!(html.contains("<h3 id=\"setup-1\">"));
+    }
+
+    #[test]
+    fn other_tags_are_left_alone() {
+        let html = "<header><hr><h7 id='x'>No</h7><h2>No id</h2></header>";
+        let (anchored, headings) = anchor_headings(html);
+        assert_eq!(anchored, html);
+        assert!(headings.is_empty());
+    }
+
+    /// The ids `markdown` generates are what `find_heading` looks for.
+    #[test]
+    fn markdown_headings() {
+        let html = markdown::to_html("# Title\n\nText\n\n## Part *one*\n");
+        let (_, headings) = anchor_headings(&html);
+        assert_eq!(ids(&headings), ["title", "part-one"]);
+        assert_eq!(headings[1].title, "Part one");
+    }
+}
@@ -0,0 +1,139 @@
+use std::path::Path;
+
+use syntect::html::{ClassStyle, ClassedHTMLGenerator};
+use syntect::parsing::SyntaxSet;
+use syntect::util::LinesWithEndings;
+
+use super::error::{BlogError, BlogErrorKind};
+
+/// The classes are prefixed so scopes such as `meta.block` don't pick up
+/// tailwind utilities like `block`. `assets/css/highlight.css` styles them.
+const CLASS_STYLE: ClassStyle = ClassStyle::SpacedPrefixed { prefix: "hl-" };
+
+/// How `markdown` renders a fenced code block with a language tag.
+const CODE_START: &str = "<pre><code class=\"language-";
+const CODE_END: &str = "</code></pre>";
+
+/// Undoes the escaping `markdown` applies to the contents of code blocks.
+pub fn unescape(code: &str) -> String {
+    code.replace("&gt;", ">")
+        .replace("&#8217;", "'")
+        .replace("&quot;", "\"")
+        .replace("&lt;", "<")
+        .replace("&amp;", "&")
+}
+
+/// Replaces the fenced code blocks of the rendered `html` with highlighted
+/// ones, using classes instead of inline colors so no JS or theme is shipped.
+///
+/// Blocks whose language isn't in `syntax_set` are left as plain code, and
+/// their language is returned so the build can warn about it. Errors point
+/// at `post`, as the rendered `html` has no lines of the markdown.
+pub fn highlight_code_blocks(
+    post: &Path,
+    html: &str,
+    syntax_set: &SyntaxSet,
+) -> Result<(String, Vec<String>), BlogError> {
+    let invalid =
+        |message: String| BlogError::new(post, BlogErrorKind::InvalidCodeBlock { message });
+    let mut highlighted = String::with_capacity(html.len());
+    let mut unknown_languages = vec![];
+    let mut rest = html;
+
+    while let Some(start) = rest.find(CODE_START) {
+        highlighted.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let block = rest;
+        let Some(end) = block.find(CODE_END) else {
+            break;
+        };
+        let Some((escaped_language, code)) = block[CODE_START.len()..end].split_once("\">") else {
+            return Err(invalid("its `<code>` tag is never closed".to_string()));
+        };
+        rest = &block[end + CODE_END.len()..];
+
+        let language = unescape(escaped_language);
+        let Some(syntax) = syntax_set.find_syntax_by_token(&language) else {
+            unknown_languages.push(language);
+            highlighted.push_str(&block[..end + CODE_END.len()]);
+            continue;
+        };
+
+        let mut generator =
+            ClassedHTMLGenerator::new_with_class_style(syntax, syntax_set, CLASS_STYLE);
+        for line in LinesWithEndings::from(&unescape(code)) {
+            generator
+                .parse_html_for_line_which_includes_newline(line)
+                .map_err(|err| invalid(format!("failed to highlight {language:?} code: {err}")))?;
+        }
+        highlighted.push_str(&format!(
+            "<pre class=\"highlight\"><code class=\"language-{escaped_language}\">{}{CODE_END}",
+            generator.finalize()
+        ));
+    }
+
+    highlighted.push_str(rest);
+    Ok((highlighted, unknown_languages))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POST: &str = "blogs/2024-11-23/index.md";
+
+    fn highlight(html: &str) -> (String, Vec<String>) {
+        highlight_code_blocks(Path::new(POST), html, &SyntaxSet::load_defaults_newlines()).unwrap()
+    }
+
+    #[test]
+    fn escapes_are_undone() {
+        assert_eq!(
+            unescape("a &lt;b&gt; &amp;&amp; &quot;c&quot; d&#8217;s &amp;lt;"),
+            "a <b> && \"c\" d's &lt;"
+        );
+    }
+
+    #[test]
+    fn known_languages_are_highlighted() {
+        let html = "<p>Before</p>\n<pre><code class=\"language-rust\">let x = &quot;&lt;a&gt;&quot;;\n</code></pre>\n<p>After</p>";
+        let (highlighted, unknown) = highlight(html);
+        assert!(unknown.is_empty());
+        assert!(highlighted
+            .starts_with("<p>Before</p>\n<pre class=\"highlight\"><code class=\"language-rust\">"));
+        assert!(highlighted.ends_with("</code></pre>\n<p>After</p>"));
+        assert!(highlighted.contains("<span class=\"hl-storage hl-type hl-rust\">let</span>"));
+        // The code is escaped again by the highlighter
+        assert!(highlighted.contains("&lt;a&gt;"), "{highlighted}");
+        assert!(!highlighted.contains("&amp;"), "{highlighted}");
+    }
+
+    #[test]
+    fn unknown_languages_are_left_as_is() {
+        let html = "<pre><code class=\"language-nope\">a &lt; b\n</code></pre><pre><code class=\"language-rs\">1\n</code></pre>";
+        let (highlighted, unknown) = highlight(html);
+        assert_eq!(unknown, ["nope"]);
+        assert!(
+            highlighted.starts_with("<pre><code class=\"language-nope\">a &lt; b\n</code></pre>")
+        );
+        assert!(highlighted.contains("<pre class=\"highlight\"><code class=\"language-rs\">"));
+    }
+
+    #[test]
+    fn plain_code_blocks_are_untouched() {
+        let html = "<pre><code>let x = 1;\n</code></pre>";
+        assert_eq!(highlight(html), (html.to_string(), vec![]));
+    }
+
+    #[test]
+    fn unclosed_code_tags_are_errors() {
+        let html = "<pre><code class=\"language-rust</code></pre>";
+        let error =
+            highlight_code_blocks(Path::new(POST), html, &SyntaxSet::load_defaults_newlines())
+                .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!("{POST}: invalid code block: its `<code>` tag is never closed")
+        );
+    }
+}
@@ -0,0 +1,554 @@
+use std::collections::HashMap;
+use std::path::{Component, Path};
+
+use super::error::{BlogError, BlogErrorKind};
+
+/// An embed a post asks for with a `{{name key="value"}}` line.
+#[derive(Debug)]
+pub enum Shortcode {
+    /// The index of the demo in the front matter.
+    WasmDemo {
+        demo: usize,
+    },
+    Youtube {
+        id: String,
+    },
+    /// `src` is a file in `assets/` or an `https://` URL.
+    Figure {
+        src: String,
+        caption: String,
+    },
+    /// The markdown between `{{note}}` and `{{/note}}`.
+    Note {
+        body: String,
+    },
+}
+
+/// A shortcode with the line of the post it was written at.
+#[derive(Debug)]
+pub struct PlacedShortcode {
+    pub line: usize,
+    pub shortcode: Shortcode,
+}
+
+type Attributes<'a> = HashMap<&'a str, &'a str>;
+
+impl Shortcode {
+    fn name(&self) -> &'static str {
+        match self {
+            Shortcode::WasmDemo { .. } => "wasm-demo",
+            Shortcode::Youtube { .. } => "youtube",
+            Shortcode::Figure { .. } => "figure",
+            Shortcode::Note { .. } => "note",
+        }
+    }
+
+    /// The code building this shortcode in `current_blogs.rs`.
+    pub fn to_rust(&self) -> String {
+        match self {
+            Shortcode::WasmDemo { demo } => format!("Shortcode::WasmDemo {{ demo: {demo} }}"),
+            Shortcode::Youtube { id } => format!("Shortcode::Youtube {{ id: {id:?} }}"),
+            Shortcode::Figure { src, caption } => {
+                format!("Shortcode::Figure {{ src: {src:?}, caption: {caption:?} }}")
+            }
+            Shortcode::Note { .. } => "Shortcode::Note".to_string(),
+        }
+    }
+
+    /// What readers without the site's JS (such as feed readers) get instead.
+    /// For notes this is their rendered body, which the client wraps.
+    fn fallback_html(
+        &self,
+        post_url: &str,
+        render: impl Fn(&str) -> Result<String, BlogError>,
+    ) -> Result<String, BlogError> {
+        Ok(match self {
+            Shortcode::WasmDemo { .. } => format!(
+                "<p><a href=\"{post_url}\">Open the post to run the interactive demo.</a></p>"
+            ),
+            Shortcode::Youtube { id } => format!(
+                "<p><a href=\"https://www.youtube.com/watch?v={id}\">Watch the video on YouTube.</a></p>"
+            ),
+            Shortcode::Figure { src, caption } => {
+                let caption = escape_html(caption);
+                format!(
+                    "<figure><img src=\"{}\" alt=\"{caption}\"><figcaption>{caption}</figcaption></figure>",
+                    escape_html(src)
+                )
+            }
+            Shortcode::Note { body } => render(body)?,
+        })
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Text `markdown` leaves untouched, so it can be found in its output.
+fn marker(index: usize) -> String {
+    format!("%%shortcode-{index}%%")
+}
+
+/// Splits `{{name key="value" other=value}}` into its name and attributes,
+/// or returns `None` if `line` isn't a shortcode.
+fn parse_tag(line: &str) -> Option<Result<(&str, Attributes<'_>), String>> {
+    let inner = line.trim().strip_prefix("{{")?.strip_suffix("}}")?.trim();
+    let (name, mut rest) = inner.split_once(char::is_whitespace).unwrap_or((inner, ""));
+
+    let mut attributes = HashMap::new();
+    loop {
+        rest = rest.trim_start();
+        if rest.is_empty() {
+            return Some(Ok((name, attributes)));
+        }
+        let Some((key, value)) = rest.split_once('=') else {
+            return Some(Err(format!("expected `key=value`, found {rest:?}")));
+        };
+        let (value, remaining) = match value.strip_prefix('"') {
+            Some(quoted) => match quoted.split_once('"') {
+                Some(split) => split,
+                None => return Some(Err(format!("unclosed quote in `{key}`"))),
+            },
+            None => value.split_once(char::is_whitespace).unwrap_or((value, "")),
+        };
+        if attributes.insert(key.trim(), value).is_some() {
+            return Some(Err(format!("`{key}` is given more than once")));
+        }
+        rest = remaining;
+    }
+}
+
+/// Checks `attributes` only has `allowed` keys and returns them in that order.
+fn take_attributes<const N: usize>(
+    mut attributes: Attributes,
+    allowed: [&str; N],
+) -> Result<[String; N], String> {
+    let values = allowed.map(|key| attributes.remove(key));
+    if let Some(key) = attributes.keys().next() {
+        return Err(format!("unknown attribute `{key}`"));
+    }
+    let mut missing = allowed
+        .iter()
+        .zip(&values)
+        .filter(|(_, value)| value.is_none());
+    if let Some((key, _)) = missing.next() {
+        return Err(format!("missing `{key}`"));
+    }
+    Ok(values.map(|value| value.unwrap().to_string()))
+}
+
+pub fn is_identifier(text: &str, allowed: impl Fn(char) -> bool) -> bool {
+    !text.is_empty()
+        && text
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || allowed(c))
+}
+
+fn parse_shortcode(
+    crate_dir: &Path,
+    demos: &[super::Demo],
+    name: &str,
+    attributes: Attributes,
+) -> Result<Shortcode, BlogErrorKind> {
+    let invalid = |message: String| BlogErrorKind::InvalidShortcode {
+        name: name.to_string(),
+        message,
+    };
+    match name {
+        "wasm-demo" => {
+            let [id] = take_attributes(attributes, ["id"]).map_err(invalid)?;
+            match demos.iter().position(|demo| demo.id == id) {
+                Some(demo) => Ok(Shortcode::WasmDemo { demo }),
+                None => Err(invalid(format!(
+                    "no demo with id {id:?} in the front matter `demos`"
+                ))),
+            }
+        }
+        "youtube" => {
+            let [id] = take_attributes(attributes, ["id"]).map_err(invalid)?;
+            if !is_identifier(&id, |c| c == '-' || c == '_') {
+                return Err(invalid(format!("invalid video id {id:?}")));
+            }
+            Ok(Shortcode::Youtube { id })
+        }
+        "figure" => {
+            let [src, caption] =
+                take_attributes(attributes, ["src", "caption"]).map_err(invalid)?;
+            if src.starts_with("https://") {
+                return Ok(Shortcode::Figure { src, caption });
+            }
+            // Only `assets/` is published, at `/assets/`
+            let Some(path) = src
+                .strip_prefix("/assets/")
+                .map(Path::new)
+                .filter(|path| path.components().all(|c| matches!(c, Component::Normal(_))))
+            else {
+                return Err(invalid(format!(
+                    "`src` {src:?} has to be in `/assets/` or an `https://` URL"
+                )));
+            };
+            if !crate_dir.join("assets").join(path).is_file() {
+                return Err(BlogErrorKind::MissingImage { image: src });
+            }
+            Ok(Shortcode::Figure { src, caption })
+        }
+        "/note" => Err(invalid("closes a note that was never opened".to_string())),
+        _ => Err(BlogErrorKind::UnknownShortcode {
+            name: name.to_string(),
+        }),
+    }
+}
+
+/// Replaces every shortcode of the markdown `body` with a marker paragraph,
+/// returning the new body and the shortcodes in order, with their line.
+///
+/// Shortcodes have to be on a line of their own, outside of code blocks.
+/// `first_line` is the line of `post` where the body starts.
+pub fn extract_shortcodes(
+    crate_dir: &Path,
+    post: &Path,
+    first_line: usize,
+    body: &str,
+    demos: &[super::Demo],
+) -> Result<(String, Vec<PlacedShortcode>), Vec<BlogError>> {
+    let mut errors = vec![];
+    let mut shortcodes = vec![];
+    let mut output = String::with_capacity(body.len());
+    let mut in_code_block = false;
+    // The note being read, with the line it was opened at
+    let mut note: Option<(usize, String)> = None;
+    let mut placed_demos = vec![];
+    let mut push_marker = |output: &mut String, line, shortcode| {
+        output.push_str(&format!("\n{}\n\n", marker(shortcodes.len())));
+        shortcodes.push(PlacedShortcode { line, shortcode });
+    };
+
+    for (index, line) in body.lines().enumerate() {
+        let line_number = first_line + index;
+        if line.trim_start().starts_with("```") {
+            in_code_block = !in_code_block;
+        }
+        let tag = if in_code_block || line.starts_with(char::is_whitespace) {
+            None
+        } else {
+            parse_tag(line)
+        };
+        let error = |kind| BlogError::new(post, kind).at_line(Some(line_number));
+
+        match (tag, &mut note) {
+            (Some(Ok(("/note", attributes))), Some(_)) if attributes.is_empty() => {
+                let (line, body) = note.take().unwrap();
+                push_marker(&mut output, line, Shortcode::Note { body });
+            }
+            (Some(_), Some(_)) => errors.push(error(BlogErrorKind::InvalidShortcode {
+                name: "note".to_string(),
+                message: "notes can't contain other shortcodes".to_string(),
+            })),
+            (None, Some((_, note_body))) => {
+                note_body.push_str(line);
+                note_body.push('\n');
+            }
+            (Some(Ok(("note", attributes))), None) if attributes.is_empty() => {
+                note = Some((line_number, String::new()));
+            }
+            (Some(Ok((name, attributes))), None) => {
+                match parse_shortcode(crate_dir, demos, name, attributes) {
+                    Ok(Shortcode::WasmDemo { demo }) if placed_demos.contains(&demo) => errors
+                        .push(error(BlogErrorKind::InvalidShortcode {
+                            name: name.to_string(),
+                            message: format!("demo {:?} is already placed", demos[demo].id),
+                        })),
+                    Ok(shortcode) => {
+                        if let Shortcode::WasmDemo { demo } = shortcode {
+                            placed_demos.push(demo);
+                        }
+                        push_marker(&mut output, line_number, shortcode)
+                    }
+                    Err(kind) => errors.push(error(kind)),
+                }
+            }
+            (Some(Err(message)), None) => errors.push(error(BlogErrorKind::InvalidShortcode {
+                name: line.trim().to_string(),
+                message,
+            })),
+            (None, None) => {
+                output.push_str(line);
+                output.push('\n');
+            }
+        }
+    }
+
+    if let Some((line, _)) = note {
+        errors.push(
+            BlogError::new(
+                post,
+                BlogErrorKind::InvalidShortcode {
+                    name: "note".to_string(),
+                    message: "expected a closing `{{/note}}`".to_string(),
+                },
+            )
+            .at_line(Some(line)),
+        );
+    }
+
+    if errors.is_empty() {
+        Ok((output, shortcodes))
+    } else {
+        Err(errors)
+    }
+}
+
+/// Swaps the marker paragraphs of the rendered `html` for the fallback of
+/// each shortcode, delimited by `<!--shortcode:N-->` and `<!--/shortcode-->`
+/// comments so the client can replace it with the real component.
+///
+/// `render` turns the markdown of notes into HTML.
+pub fn insert_shortcodes(
+    post: &Path,
+    html: &str,
+    shortcodes: &[PlacedShortcode],
+    post_url: &str,
+    render: impl Fn(&str) -> Result<String, BlogError>,
+) -> Result<String, BlogError> {
+    let mut html = html.to_string();
+    for (index, PlacedShortcode { line, shortcode }) in shortcodes.iter().enumerate() {
+        let paragraph = format!("<p>{}</p>", marker(index));
+        if !html.contains(&paragraph) {
+            // `markdown` merged it into a list or a paragraph
+            return Err(BlogError::new(
+                post,
+                BlogErrorKind::InvalidShortcode {
+                    name: shortcode.name().to_string(),
+                    message: "has to be separated from the text around it by blank lines"
+                        .to_string(),
+                },
+            )
+            .at_line(Some(*line)));
+        }
+        let fallback = shortcode.fallback_html(post_url, &render).map_err(|err| {
+            let line = err.line.or(Some(*line));
+            err.at_line(line)
+        })?;
+        html = html.replacen(
+            &paragraph,
+            &format!("<!--shortcode:{index}-->{fallback}<!--/shortcode-->"),
+            1,
+        );
+    }
+    Ok(html)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POST: &str = "blogs/2024-11-23/index.md";
+
+    fn crate_dir() -> &'static Path {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+    }
+
+    fn demo(id: &str) -> super::super::Demo {
+        super::super::Demo {
+            module: "triangle-demo".to_string(),
+            id: id.to_string(),
+            scene: None,
+            aspect_ratio: None,
+            max_resolution: None,
+            fallback_image: None,
+            fallback_text: None,
+        }
+    }
+
+    fn extract(body: &str) -> Result<(String, Vec<PlacedShortcode>), Vec<String>> {
+        extract_shortcodes(crate_dir(), Path::new(POST), 10, body, &[demo("triangle")])
+            .map_err(|errors| errors.iter().map(ToString::to_string).collect())
+    }
+
+    fn figure(src: &str) -> Result<Shortcode, String> {
+        let attributes = HashMap::from([("src", src), ("caption", "A figure")]);
+        parse_shortcode(crate_dir(), &[], "figure", attributes).map_err(|kind| kind.to_string())
+    }
+
+    #[test]
+    fn tags() {
+        let (name, attributes) = parse_tag(r#" {{youtube id="a b" start=10}} "#)
+            .unwrap()
+            .unwrap();
+        assert_eq!(name, "youtube");
+        assert_eq!(attributes, HashMap::from([("id", "a b"), ("start", "10")]));
+        assert_eq!(parse_tag("{{note}}").unwrap().unwrap().0, "note");
+
+        assert!(parse_tag("Some {{text}}").is_none());
+        assert!(parse_tag("{{text").is_none());
+        assert_eq!(
+            parse_tag("{{a key}}").unwrap().unwrap_err(),
+            "expected `key=value`, found \"key\""
+        );
+        assert_eq!(
+            parse_tag(r#"{{a key="value}}"#).unwrap().unwrap_err(),
+            "unclosed quote in `key`"
+        );
+        assert_eq!(
+            parse_tag("{{a key=1 key=2}}").unwrap().unwrap_err(),
+            "`key` is given more than once"
+        );
+    }
+
+    #[test]
+    fn shortcodes_become_markers() {
+        let body =
+            "Intro\n\n{{youtube id=\"dQw4w9WgXcQ\"}}\n\n{{wasm-demo id=\"triangle\"}}\nOutro\n";
+        let (output, shortcodes) = extract(body).unwrap();
+        assert_eq!(
+            output,
+            "Intro\n\n\n%%shortcode-0%%\n\n\n\n%%shortcode-1%%\n\nOutro\n"
+        );
+        assert_eq!(
+            shortcodes
+                .iter()
+                .map(|placed| placed.line)
+                .collect::<Vec<_>>(),
+            [12, 14]
+        );
+        assert!(
+            matches!(&shortcodes[0].shortcode, Shortcode::Youtube { id } if id == "dQw4w9WgXcQ")
+        );
+        assert!(matches!(
+            shortcodes[1].shortcode,
+            Shortcode::WasmDemo { demo: 0 }
+        ));
+    }
+
+    #[test]
+    fn code_blocks_and_indented_lines_are_left_alone() {
+        let body = "```\n{{youtube id=\"a\"}}\n```\n    {{youtube id=\"b\"}}\n";
+        let (output, shortcodes) = extract(body).unwrap();
+        assert_eq!(output, body);
+        assert!(shortcodes.is_empty());
+    }
+
+    #[test]
+    fn notes_keep_their_markdown() {
+        let (output, shortcodes) = extract("{{note}}\n*Careful*\n\nTwice\n{{/note}}\n").unwrap();
+        assert_eq!(output, "\n%%shortcode-0%%\n\n");
+        assert_eq!(shortcodes[0].line, 10);
+        assert!(
+            matches!(&shortcodes[0].shortcode, Shortcode::Note { body } if body == "*Careful*\n\nTwice\n")
+        );
+    }
+
+    #[test]
+    fn every_invalid_shortcode_is_reported() {
+        let body = "{{gallery}}\n\
+                    {{youtube id=\"a?b\"}}\n\
+                    {{wasm-demo id=\"triangle\"}}\n\
+                    {{wasm-demo id=\"triangle\"}}\n\
+                    {{wasm-demo id=\"square\"}}\n\
+                    {{/note}}\n\
+                    {{note}}\n\
+                    {{youtube id=\"a\"}}\n";
+        assert_eq!(
+            extract(body).unwrap_err(),
+            [
+                format!("{POST}:10: unknown shortcode `gallery`, expected wasm-demo, youtube, figure or note"),
+                format!("{POST}:11: invalid `youtube` shortcode: invalid video id \"a?b\""),
+                format!("{POST}:13: invalid `wasm-demo` shortcode: demo \"triangle\" is already placed"),
+                format!("{POST}:14: invalid `wasm-demo` shortcode: no demo with id \"square\" in the front matter `demos`"),
+                format!("{POST}:15: invalid `/note` shortcode: closes a note that was never opened"),
+                format!("{POST}:17: invalid `note` shortcode: notes can't contain other shortcodes"),
+                format!("{POST}:16: invalid `note` shortcode: expected a closing `{{{{/note}}}}`"),
+            ]
+        );
+    }
+
+    #[test]
+    fn figures_are_published_assets_or_https() {
+        assert!(figure("/assets/img/keysight_logo.svg").is_ok());
+        assert!(figure("https://example.com/a.png").is_ok());
+        assert_eq!(
+            figure("/assets/img/missing.png").unwrap_err(),
+            "image \"/assets/img/missing.png\" doesn't exist"
+        );
+        // Exist on disk, but aren't served
+        for src in [
+            "/raw_assets/img/top.jpg",
+            "/assets/../raw_assets/img/top.jpg",
+            "assets/img/keysight_logo.svg",
+            "http://example.com/a.png",
+        ] {
+            assert_eq!(
+                figure(src).unwrap_err(),
+                format!(
+                    "invalid `figure` shortcode: `src` {src:?} has to be in `/assets/` or an `https://` URL"
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn markers_are_replaced_by_fallbacks() {
+        let (body, shortcodes) =
+            extract("Intro\n\n{{figure src=\"https://example.com/a.png\" caption=\"A & B\"}}\n\n{{note}}\n*Careful*\n{{/note}}\n")
+                .unwrap();
+        let html = insert_shortcodes(
+            Path::new(POST),
+            &markdown::to_html(&body),
+            &shortcodes,
+            "/blog/2024/11/23/post",
+            |markdown: &str| Ok(markdown::to_html(markdown)),
+        )
+        .unwrap();
+        assert!(html.contains(
+            "<!--shortcode:0--><figure><img src=\"https://example.com/a.png\" alt=\"A &amp; B\">\
+             <figcaption>A &amp; B</figcaption></figure><!--/shortcode-->"
+        ), "{html}");
+        assert!(
+            html.contains("<!--shortcode:1--><p><em>Careful</em></p>"),
+            "{html}"
+        );
+        assert!(!html.contains("%%shortcode"));
+    }
+
+    #[test]
+    fn markers_merged_into_text_are_errors() {
+        let (body, shortcodes) = extract("- Item\n{{youtube id=\"a\"}}\n").unwrap();
+        let merged = body.replace("\n\n%%", "\n%%");
+        let error = insert_shortcodes(
+            Path::new(POST),
+            &markdown::to_html(&merged),
+            &shortcodes,
+            "/blog/2024/11/23/post",
+            |markdown: &str| Ok(markdown::to_html(markdown)),
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!("{POST}:11: invalid `youtube` shortcode: has to be separated from the text around it by blank lines")
+        );
+    }
+    #[test]
+    fn note_render_errors_point_at_the_note() {
+        let (body, shortcodes) = extract("Intro\n\n{{note}}\nBody\n{{/note}}\n").unwrap();
+        let error = insert_shortcodes(
+            Path::new(POST),
+            &markdown::to_html(&body),
+            &shortcodes,
+            "/blog/2024/11/23/post",
+            |_: &str| {
+                Err(BlogError::new(
+                    POST,
+                    BlogErrorKind::InvalidCodeBlock {
+                        message: "broken".to_string(),
+                    },
+                ))
+            },
+        )
+        .unwrap_err();
+        assert_eq!(error.line, Some(shortcodes[0].line));
+    }
+}
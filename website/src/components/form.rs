@@ -1,3 +1,5 @@
+pub mod checkbox;
+pub mod input_number;
 pub mod input_text;
 pub mod range;
 pub mod read_only_input_text;
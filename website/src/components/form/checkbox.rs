@@ -0,0 +1,36 @@
+use dioxus::prelude::*;
+
+#[derive(Debug, Clone, PartialEq, Props)]
+pub struct CheckboxProps {
+    pub source: Signal<bool>,
+    #[props(into)]
+    pub label: String,
+    #[props(into, default)]
+    pub id: Option<String>,
+}
+
+#[component]
+pub fn Checkbox(props: CheckboxProps) -> Element {
+    let CheckboxProps {
+        mut source,
+        label,
+        id,
+    } = props;
+
+    rsx! {
+        div { class: "flex flex-row items-center py-2 mt-2",
+            input {
+                r#type: "checkbox",
+                class: "w-4 h-4 cursor-pointer",
+                id: id.clone(),
+                checked: source(),
+                onchange: move |event| source.set(event.checked()),
+            }
+            label {
+                r#for: id,
+                class: "ml-2 cursor-pointer text-primary dark:text-gray-200",
+                "{label}"
+            }
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use dioxus::prelude::*;
+
+#[derive(Debug, Clone, PartialEq, Props)]
+pub struct InputNumberProps {
+    pub source: Signal<i64>,
+    pub min: i64,
+    pub max: i64,
+    #[props(into, default)]
+    pub id: Option<String>,
+}
+
+/// A number input that keeps `source` between `min` and `max`.
+#[component]
+pub fn InputNumber(props: InputNumberProps) -> Element {
+    let InputNumberProps {
+        mut source,
+        min,
+        max,
+        id,
+    } = props;
+
+    rsx! {
+        input {
+            r#type: "number",
+            class: "block w-full px-4 py-2 mt-2 text-gray-700 bg-white border border-gray-300 rounded-md dark:bg-gray-800 dark:text-gray-300 dark:border-gray-600 focus:border-blue-500 dark:focus:border-blue-500 focus:outline-none focus:ring",
+            id,
+            min,
+            max,
+            value: source(),
+            onchange: move |event| {
+                let value = event.value().parse::<i64>().unwrap_or(min);
+                source.set(value.clamp(min, max));
+            },
+        }
+    }
+}
@@ -26,7 +26,7 @@ pub fn InputText(props: InputTextProps) -> Element {
             placeholder: "{placeholder}",
             onchange: move |event| source.set(event.value()),
             oninput: move |event| source.set(event.value()),
-            "{value}"
+            value: "{value}",
         }
     }
 }
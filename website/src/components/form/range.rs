@@ -5,8 +5,6 @@ pub struct RangeProps {
     pub source: Signal<i64>,
     pub min: i64,
     pub max: i64,
-    #[props(default = max)]
-    pub initial_value: i64,
     #[props(default = 1)]
     pub step: i64,
     #[props(into, default)]
@@ -19,7 +17,6 @@ pub fn Range(props: RangeProps) -> Element {
         mut source,
         min,
         max,
-        initial_value,
         step,
         id,
     } = props;
@@ -34,7 +31,7 @@ pub fn Range(props: RangeProps) -> Element {
                 step,
                 class: "block w-full py-2 mt-2 text-gray-700 bg-white border border-gray-300 rounded-md dark:bg-gray-800 dark:text-gray-300 dark:border-gray-600 focus:border-blue-500 dark:focus:border-blue-500 focus:outline-none focus:ring",
                 id: id.clone(),
-                initial_value,
+                value: source(),
             }
             p { class: "py-2 mt-2 ml-2 text-primary dark:text-gray-200", "{source}" }
         }
@@ -2,6 +2,7 @@ use dioxus::logger::tracing::{info, Level};
 use dioxus::prelude::*;
 
 mod components;
+mod prerender;
 mod sections;
 mod utils;
 
@@ -17,59 +18,83 @@ fn config_logger() {
     info!("starting app");
 }
 
+/// Writes `contents` to `file`, creating its parent directories.
 #[cfg(feature = "generate_htmls")]
-fn generate_blog_files(docs_dir: &std::path::Path, index_file: &std::path::PathBuf) {
-    let blog_dir = docs_dir.join("blog");
-    for entry in std::fs::read_dir("website/assets/blogs").unwrap() {
-        let origin_path = entry.unwrap().path();
-        if !origin_path.is_dir() {
-            continue;
-        }
+fn write_route_file(file: &std::path::Path, contents: &str) {
+    let parent_dir = file.parent().unwrap();
+    if !parent_dir.exists() {
+        std::fs::create_dir_all(parent_dir).expect("failed to create parent dir for a file");
+    } else {
+        assert!(parent_dir.is_dir());
+    }
 
-        let year_month_day = origin_path.file_name().unwrap();
-
-        let year_month_day = year_month_day
-            .to_str()
-            .unwrap()
-            .split('-')
-            .collect::<Vec<_>>();
-
-        let day_dir = blog_dir
-            .join(year_month_day[0])
-            .join(year_month_day[1])
-            .join(year_month_day[2]);
-        if !day_dir.exists() {
-            std::fs::create_dir_all(day_dir.clone())
-                .expect("failed to create day_dir dir for a post");
-        } else {
-            assert!(day_dir.is_dir());
-        }
+    let file_str = file.to_str().unwrap();
+    info!("file: {file_str}");
+    std::fs::write(file, contents).expect("failed to write route file");
+}
 
-        let file = day_dir.join("index.html");
-        let file_str = file.to_str().unwrap();
-        info!("file: {file_str}");
-
-        let static_version = std::env::current_dir()
-            .unwrap()
-            .join("static")
-            .join("blog")
-            .join(year_month_day[0])
-            .join(year_month_day[1])
-            .join(year_month_day[2])
-            .join("index.html")
-            .join("index.html");
-
-        if static_version.is_file() {
-            info!(
-                "found static version at: {}",
-                static_version.to_str().unwrap()
-            );
-            std::fs::copy(static_version, file)
-                .expect("failed to copy static version of page to new route");
-        } else {
-            std::fs::copy(index_file, file).expect("failed to copy index.html to new route");
+#[cfg(feature = "generate_htmls")]
+fn generate_blog_files(docs_dir: &std::path::Path, template: &str) {
+    let blog_ids = sections::blog::entries::get_blogs().keys().copied();
+
+    for blog_id in blog_ids {
+        let route: Vec<String> = sections::ActiveSection::Blog(Some(blog_id)).into();
+        let route = route.join("/");
+        let page = prerender::PrerenderedPage::render(&format!("/{route}"));
+        write_route_file(&docs_dir.join(route), &page.fill_template(template));
+
+        // Old date-only links get the bare SPA, which redirects them to the post.
+        let legacy_route = blog_id.date.legacy_route().join("/");
+        write_route_file(&docs_dir.join(legacy_route), template);
+    }
+}
+
+/// Renders `sitemap.txt` and `sitemap.xml`, listing every page under `site_url`.
+#[cfg(feature = "generate_htmls")]
+fn render_sitemaps(site_url: &str) -> (String, String) {
+    let blogs = sections::blog::entries::get_blogs();
+    let newest_post = blogs.keys().map(|blog_id| blog_id.date).max();
+
+    // (route, last modification date)
+    let mut pages = vec![(String::new(), None)];
+    for route in sections::ActiveSection::all_routes() {
+        let last_modified = match sections::ActiveSection::try_from(route.as_slice()) {
+            Ok(sections::ActiveSection::Blog(None)) => newest_post,
+            _ => None,
+        };
+        pages.push((route.join("/"), last_modified));
+    }
+    let mut blog_ids = blogs.keys().copied().collect::<Vec<_>>();
+    blog_ids.sort_by_key(|blog_id| (blog_id.date, blog_id.slug));
+    for blog_id in blog_ids {
+        let route: Vec<String> = sections::ActiveSection::Blog(Some(blog_id)).into();
+        pages.push((route.join("/"), Some(blog_id.date)));
+    }
+
+    let mut sitemap_txt = String::new();
+    let mut sitemap_xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+    for (route, last_modified) in pages {
+        let url = format!("{site_url}/{route}");
+        sitemap_txt += &format!("{url}\n");
+        sitemap_xml += &format!("  <url>\n    <loc>{url}</loc>\n");
+        if let Some(date) = last_modified {
+            sitemap_xml += &format!("    <lastmod>{}</lastmod>\n", date.iso_date());
         }
+        sitemap_xml += "  </url>\n";
     }
+    sitemap_xml += "</urlset>\n";
+    (sitemap_txt, sitemap_xml)
+}
+
+#[cfg(feature = "generate_htmls")]
+fn generate_sitemaps(docs_dir: &std::path::Path) {
+    let site_url = format!("https://{}", include_str!("../assets/CNAME").trim());
+    let (sitemap_txt, sitemap_xml) = render_sitemaps(&site_url);
+    std::fs::write(docs_dir.join("sitemap.txt"), sitemap_txt).expect("failed to write sitemap.txt");
+    std::fs::write(docs_dir.join("sitemap.xml"), sitemap_xml).expect("failed to write sitemap.xml");
 }
 
 #[cfg(feature = "generate_htmls")]
@@ -88,53 +113,23 @@ fn generate_all_route_files() {
         "index.html not found. Please run `./build.sh`"
     );
 
+    // Every page is rendered from the SPA, so keep it before overwriting the root
+    let template = std::fs::read_to_string(&index_file).expect("failed to read index.html");
+
     for route in sections::ActiveSection::all_routes() {
         let route = route.join("/");
-        let file = docs_dir.join(&route);
-        let parent_dir = file.parent().unwrap();
-        let file_str = file.to_str().unwrap();
-        info!("file: {file_str}");
-        if !parent_dir.exists() {
-            std::fs::create_dir_all(parent_dir).expect("failed to create parent dir for a file");
-        } else {
-            assert!(parent_dir.is_dir());
-        }
-
-        let static_version = std::env::current_dir()
-            .unwrap()
-            .join("static")
-            .join(route)
-            .join("index.html");
-
-        if static_version.is_file() {
-            info!(
-                "found static version at: {}",
-                static_version.to_str().unwrap()
-            );
-            std::fs::copy(static_version, file)
-                .expect("failed to copy static version of page to new route");
-        } else {
-            std::fs::copy(&index_file, file).expect("failed to copy index.html to new route");
-        }
+        let page = prerender::PrerenderedPage::render(&format!("/{route}"));
+        write_route_file(&docs_dir.join(route), &page.fill_template(&template));
     }
 
     // Special case for posts
-    generate_blog_files(&docs_dir, &index_file);
+    generate_blog_files(&docs_dir, &template);
 
-    // Special case for the root
-    let static_version = std::env::current_dir()
-        .unwrap()
-        .join("static")
-        .join("index.html");
+    generate_sitemaps(&docs_dir);
 
-    if static_version.is_file() {
-        info!(
-            "found static version at: {}",
-            static_version.to_str().unwrap()
-        );
-        std::fs::copy(static_version, index_file)
-            .expect("failed to copy static version of page to new route");
-    }
+    // Special case for the root
+    let page = prerender::PrerenderedPage::render("/");
+    write_route_file(&index_file, &page.fill_template(&template));
 }
 
 #[cfg(all(
@@ -161,6 +156,13 @@ fn main() {
         })
         .launch(App);
     */
+    #[cfg(feature = "web")]
+    if prerender::is_prerendered() {
+        dioxus::LaunchBuilder::new()
+            .with_cfg(dioxus::web::Config::new().hydrate(true))
+            .launch(App);
+        return;
+    }
     launch(App);
 }
 /*
@@ -211,9 +213,12 @@ fn MainSectionDisplayed(route: Vec<String>, current_section: sections::ActiveSec
         sections::ActiveSection::PasswordGenerator => rsx! {
             main { sections::password_generator::PasswordGenerator {} }
         },
-        sections::ActiveSection::Blog(blog_date) => rsx! {
+        sections::ActiveSection::ShaderPlayground => rsx! {
+            main { sections::shader_playground::ShaderPlayground {} }
+        },
+        sections::ActiveSection::Blog(blog_id) => rsx! {
             main {
-                sections::blog::Blog { blog_date }
+                sections::blog::Blog { blog_id }
             }
         },
     }
@@ -235,6 +240,10 @@ pub fn App() -> Element {
     rsx! {
         document::Stylesheet { href: asset!("assets/css/tailwind.css") }
         document::Stylesheet { href: asset!("assets/css/hamburgers-min.css") }
+        document::Stylesheet { href: asset!("assets/css/highlight.css") }
+        document::Stylesheet { href: asset!("assets/css/blog.css") }
+        document::Stylesheet { href: asset!("assets/css/playground.css") }
+        document::Stylesheet { href: asset!("assets/css/password.css") }
         Router::<Route> {}
     }
 }
@@ -246,6 +255,18 @@ fn NavBar(route: Vec<String>) -> Element {
 
     info!("current_section: {:?} [{:?}]", current_section, route);
 
+    // Send aliases (such as date-only blog links) to their canonical URL
+    use_effect(use_reactive!(|route| {
+        if let Ok(section) = sections::ActiveSection::try_from(route.as_slice()) {
+            let canonical_route: Vec<String> = section.into();
+            if canonical_route != route {
+                navigator().replace(Route::NavBar {
+                    route: canonical_route,
+                });
+            }
+        }
+    }));
+
     let titleBarEntries = vec![
         components::title_bar::TitleEntry::new(
             "About Me",
@@ -257,6 +278,11 @@ fn NavBar(route: Vec<String>) -> Element {
             sections::ActiveSection::PasswordGenerator,
             current_section,
         ),
+        components::title_bar::TitleEntry::new(
+            "Shader Playground",
+            sections::ActiveSection::ShaderPlayground,
+            current_section,
+        ),
         components::title_bar::TitleEntry::new(
             "Blog",
             sections::ActiveSection::Blog(None),
@@ -269,3 +295,59 @@ fn NavBar(route: Vec<String>) -> Element {
         MainSectionDisplayed { route, current_section }
     }
 }
+
+#[cfg(all(test, feature = "generate_htmls"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sitemaps_list_every_page() {
+        let (txt, xml) = render_sitemaps("https://example.com");
+        let urls = txt.lines().collect::<Vec<_>>();
+        assert_eq!(urls[0], "https://example.com/");
+        for route in sections::ActiveSection::all_routes() {
+            let url = format!("https://example.com/{}", route.join("/"));
+            assert!(urls.contains(&url.as_str()), "{url} is missing");
+        }
+
+        let blogs = sections::blog::entries::get_blogs();
+        assert_eq!(
+            urls.len(),
+            1 + sections::ActiveSection::all_routes().len() + blogs.len()
+        );
+        assert_eq!(xml.matches("<url>").count(), urls.len());
+        for url in &urls {
+            assert!(
+                xml.contains(&format!("<loc>{url}</loc>")),
+                "{url} is missing"
+            );
+        }
+        assert!(xml.starts_with("<?xml"));
+        assert!(xml.ends_with("</urlset>\n"));
+    }
+
+    #[test]
+    fn posts_are_dated() {
+        let (_, xml) = render_sitemaps("https://example.com");
+        let blogs = sections::blog::entries::get_blogs();
+        for blog_id in blogs.keys().copied() {
+            let route: Vec<String> = sections::ActiveSection::Blog(Some(blog_id)).into();
+            let entry = format!(
+                "<loc>https://example.com/{}</loc>\n    <lastmod>{}</lastmod>",
+                route.join("/"),
+                blog_id.date.iso_date()
+            );
+            assert!(xml.contains(&entry), "{entry} is missing");
+        }
+
+        // The blog index changes with its newest post
+        let newest = blogs.keys().map(|blog_id| blog_id.date).max().unwrap();
+        let route: Vec<String> = sections::ActiveSection::Blog(None).into();
+        let entry = format!(
+            "<loc>https://example.com/{}</loc>\n    <lastmod>{}</lastmod>",
+            route.join("/"),
+            newest.iso_date()
+        );
+        assert!(xml.contains(&entry), "{entry} is missing");
+    }
+}
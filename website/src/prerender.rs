@@ -0,0 +1,166 @@
+//! Pre-rendering of every route into static HTML, which the client then
+//! hydrates instead of rendering from scratch.
+
+/// Returns a value computed while pre-rendering the page, so the first render
+/// on the client matches the static HTML it hydrates.
+///
+/// `prerender` only runs in the `generate_htmls` binary. On the client the
+/// value is read back from the hydration data, and is `None` if the page
+/// wasn't pre-rendered or if the component isn't part of the first render.
+/// Values are matched by the order in which the hooks run, so this must be
+/// called unconditionally.
+#[cfg(feature = "generate_htmls")]
+pub fn use_prerendered<T: serde::Serialize + Clone + 'static>(
+    prerender: impl FnOnce() -> Option<T>,
+) -> Option<T> {
+    use dioxus::prelude::*;
+    use_hook(|| {
+        let value = prerender();
+        if let Some(data) = try_consume_context::<HydrationData>() {
+            data.push(value.as_ref());
+        }
+        value
+    })
+}
+
+#[cfg(all(feature = "web", not(feature = "generate_htmls")))]
+pub fn use_prerendered<T: serde::de::DeserializeOwned + Clone + 'static>(
+    _prerender: impl FnOnce() -> Option<T>,
+) -> Option<T> {
+    dioxus::prelude::use_hook(|| dioxus_web::take_server_data::<T>().ok().flatten())
+}
+
+#[cfg(not(any(feature = "web", feature = "generate_htmls")))]
+pub fn use_prerendered<T: Clone + 'static>(_prerender: impl FnOnce() -> Option<T>) -> Option<T> {
+    None
+}
+
+/// Whether the page was pre-rendered and should be hydrated rather than
+/// rendered from scratch.
+#[cfg(feature = "web")]
+pub fn is_prerendered() -> bool {
+    web_sys::window()
+        .map(|window| {
+            web_sys::js_sys::Reflect::has(&window, &"initial_dioxus_hydration_data".into())
+                .unwrap_or(false)
+        })
+        .unwrap_or(false)
+}
+
+/// Values captured by [`use_prerendered`], in the format the dioxus web
+/// renderer expects for hydration.
+#[cfg(feature = "generate_htmls")]
+#[derive(Clone, Default)]
+struct HydrationData(std::rc::Rc<std::cell::RefCell<Vec<Option<Vec<u8>>>>>);
+
+#[cfg(feature = "generate_htmls")]
+impl HydrationData {
+    fn push<T: serde::Serialize>(&self, value: Option<&T>) {
+        let serialized = value.map(|value| {
+            let mut serialized = vec![];
+            ciborium::into_writer(value, &mut serialized)
+                .expect("failed to serialize hydration data");
+            serialized
+        });
+        self.0.borrow_mut().push(serialized);
+    }
+
+    fn serialized(&self) -> String {
+        use base64::Engine;
+        // The first entry is reserved for an error thrown while rendering
+        let mut data = vec![None];
+        data.extend(self.0.borrow().iter().cloned());
+        let mut serialized = vec![];
+        ciborium::into_writer(&data, &mut serialized).expect("failed to serialize hydration data");
+        base64::engine::general_purpose::STANDARD.encode(serialized)
+    }
+}
+
+/// Collects the elements components add to the `<head>` (such as stylesheets)
+/// so they can be part of the static page.
+#[cfg(feature = "generate_htmls")]
+#[derive(Default)]
+struct PrerenderDocument {
+    head: std::cell::RefCell<String>,
+}
+
+#[cfg(feature = "generate_htmls")]
+impl dioxus::document::Document for PrerenderDocument {
+    fn eval(&self, js: String) -> dioxus::document::Eval {
+        dioxus::document::NoOpDocument.eval(js)
+    }
+
+    fn create_head_element(
+        &self,
+        name: &str,
+        attributes: &[(&str, String)],
+        contents: Option<String>,
+    ) {
+        let mut head = self.head.borrow_mut();
+        head.push_str(&format!("<{name}"));
+        for (attribute, value) in attributes {
+            head.push_str(&format!(
+                " {attribute}=\"{}\"",
+                value.replace('&', "&amp;").replace('"', "&quot;")
+            ));
+        }
+        head.push('>');
+        if let Some(contents) = contents {
+            head.push_str(&format!("{contents}</{name}>"));
+        }
+    }
+}
+
+/// The pieces of a pre-rendered route that get spliced into the SPA's
+/// `index.html`.
+#[cfg(feature = "generate_htmls")]
+pub struct PrerenderedPage {
+    pub head: String,
+    pub body: String,
+    pub hydration_data: String,
+}
+
+#[cfg(feature = "generate_htmls")]
+impl PrerenderedPage {
+    /// Renders `App` as it would look when opening `path`.
+    pub fn render(path: &str) -> Self {
+        use dioxus::prelude::*;
+        use std::rc::Rc;
+
+        let document = Rc::new(PrerenderDocument::default());
+        let hydration_data = HydrationData::default();
+
+        let mut dom = VirtualDom::new(crate::App)
+            .with_root_context(
+                Rc::new(dioxus::history::MemoryHistory::with_initial_path(path)) as Rc<dyn History>,
+            )
+            .with_root_context(document.clone() as Rc<dyn dioxus::document::Document>)
+            .with_root_context(hydration_data.clone());
+        dom.rebuild_in_place();
+
+        let body = dioxus::ssr::pre_render(&dom);
+        let head = document.head.borrow().clone();
+        Self {
+            head,
+            body,
+            hydration_data: hydration_data.serialized(),
+        }
+    }
+
+    /// Fills the SPA `template` with the pre-rendered markup.
+    pub fn fill_template(&self, template: &str) -> String {
+        const MAIN: &str = r#"<div id="main"></div>"#;
+        assert!(
+            template.contains(MAIN),
+            "index.html doesn't have an empty main div to render into"
+        );
+        template.replacen("</head>", &format!("{}</head>", self.head), 1).replacen(
+            MAIN,
+            &format!(
+                r#"<div id="main">{}</div><script>window.initial_dioxus_hydration_data="{}";</script>"#,
+                self.body, self.hydration_data
+            ),
+            1,
+        )
+    }
+}
@@ -1,8 +1,9 @@
 pub mod about_me;
 pub mod blog;
 pub mod password_generator;
+pub mod shader_playground;
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct BlogDate {
     year: u64,
     month: u8,
@@ -15,11 +16,44 @@ impl std::fmt::Display for BlogDate {
     }
 }
 
+impl BlogDate {
+    /// The date as `YYYY-MM-DD`.
+    pub fn iso_date(self) -> String {
+        format!("{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+
+    /// Route posts had before they were identified by a slug as well.
+    pub fn legacy_route(self) -> Vec<String> {
+        vec![
+            "blog".to_string(),
+            self.year.to_string(),
+            self.month.to_string(),
+            self.day.to_string(),
+            "index.html".to_string(),
+        ]
+    }
+}
+
+/// Identifies a post: several posts can share a date, but not a slug on that
+/// date.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BlogId {
+    pub date: BlogDate,
+    pub slug: &'static str,
+}
+
+impl std::fmt::Display for BlogId {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}/{}", self.date, self.slug)
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ActiveSection {
     AboutMe,
     PasswordGenerator,
-    Blog(Option<BlogDate>),
+    ShaderPlayground,
+    Blog(Option<BlogId>),
 }
 
 impl ActiveSection {
@@ -27,6 +61,7 @@ impl ActiveSection {
         [
             ActiveSection::AboutMe,
             ActiveSection::PasswordGenerator,
+            ActiveSection::ShaderPlayground,
             ActiveSection::Blog(None),
         ]
         .into_iter()
@@ -55,12 +90,14 @@ impl From<ActiveSection> for Vec<String> {
         match active_section {
             ActiveSection::AboutMe => vec!["about_me.html".to_string()],
             ActiveSection::PasswordGenerator => vec!["password_generator.html".to_string()],
+            ActiveSection::ShaderPlayground => vec!["shader_playground.html".to_string()],
             ActiveSection::Blog(None) => vec!["blog.html".to_string()],
-            ActiveSection::Blog(Some(blog_date)) => vec![
+            ActiveSection::Blog(Some(blog_id)) => vec![
                 "blog".to_string(),
-                blog_date.year.to_string(),
-                blog_date.month.to_string(),
-                blog_date.day.to_string(),
+                blog_id.date.year.to_string(),
+                blog_id.date.month.to_string(),
+                blog_id.date.day.to_string(),
+                blog_id.slug.to_string(),
                 "index.html".to_string(),
             ],
         }
@@ -85,10 +122,19 @@ impl TryFrom<&[String]> for ActiveSection {
         match str_vec.as_slice() {
             ["about_me.html"] => Ok(Self::AboutMe),
             ["password_generator.html"] => Ok(Self::PasswordGenerator),
+            ["shader_playground.html"] => Ok(Self::ShaderPlayground),
             ["blog.html"] => Ok(Self::Blog(None)),
-            ["blog", year, month, day, "index.html"] => {
-                Ok(Self::Blog(parse_blog_date(year, month, day)))
-            }
+            ["blog", year, month, day, slug, "index.html"] => Ok(Self::Blog(
+                parse_blog_date(year, month, day)
+                    .and_then(|date| blog::entries::find_blog(date, slug))
+                    .map(|blog_entry| blog_entry.blog_id),
+            )),
+            // Posts used to be keyed by date only, keep those links working
+            ["blog", year, month, day, "index.html"] => Ok(Self::Blog(
+                parse_blog_date(year, month, day)
+                    .and_then(blog::entries::first_blog_of_day)
+                    .map(|blog_entry| blog_entry.blog_id),
+            )),
             _ => Err(format!("Couldn't navigate to {:?}", values)),
         }
     }
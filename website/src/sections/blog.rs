@@ -6,52 +6,23 @@ use wasm_bindgen::prelude::*;
 #[cfg(feature = "web")]
 use web_sys::wasm_bindgen::JsCast;
 
-#[cfg(feature = "web")]
-use dioxus_web::WebEventExt;
+mod demo;
+pub mod entries;
 
-mod entries;
-
-#[cfg(feature = "web")]
-async fn run(extern_module: String, id: String) -> dioxus::Result<JsValue> {
-    use web_sys::js_sys;
-    info!("id: {}", id);
-    // import init, {{ run }} from '{}';
-    // await module.init();
-    let result = js_sys::eval(
-        format!(
-            "
-        async function run_async() {{
-            const {{
-                default: init,
-                run,
-            }} = await import('{}');
-            await init();
-            return await run('{}');
-        }}
-        run_async",
-            extern_module, id
-        )
-        .as_str(),
-    )
-    .map_err(|err| dioxus::CapturedError::from_display(format!("{:#?}", err)))?
-    .dyn_into::<js_sys::Function>()
-    .map_err(|err| dioxus::CapturedError::from_display(format!("{:#?}", err)))?;
-
-    let result = result
-        .call0(&JsValue::null())
-        .map_err(|err| dioxus::CapturedError::from_display(format!("{:#?}", err)))?
-        .dyn_into::<js_sys::Promise>()
-        .map(wasm_bindgen_futures::JsFuture::from)
-        .map_err(|err| dioxus::CapturedError::from_display(format!("{:#?}", err)))?
-        .await
-        .map_err(|err| dioxus::CapturedError::from_display(format!("{:#?}", err)))?;
-
-    info!("Result: {:?}", result);
-    Ok(result)
-}
+pub use demo::DemoCanvas;
+mod shortcodes;
+mod toc;
 
 #[component]
-fn BlogMarkdown(blog_date: sections::BlogDate) -> Element {
+fn BlogMarkdown(blog_id: sections::BlogId) -> Element {
+    let prerendered = crate::prerender::use_prerendered(|| {
+        #[cfg(feature = "generate_htmls")]
+        let html = std::fs::read_to_string(entries::html_source_path(blog_id)).ok();
+        #[cfg(not(feature = "generate_htmls"))]
+        let html: Option<String> = None;
+        html
+    });
+
     #[cfg(feature = "web")]
     let markdown: Resource<Result<String, JsValue>> = use_resource(move || async move {
         let opts = web_sys::RequestInit::new();
@@ -59,7 +30,7 @@ fn BlogMarkdown(blog_date: sections::BlogDate) -> Element {
         opts.set_method("GET");
         opts.set_mode(web_sys::RequestMode::SameOrigin);
 
-        let html = entries::get_blog(blog_date).html;
+        let html = entries::get_blog(blog_id).html;
         let url = html.to_string();
 
         let request = web_sys::Request::new_with_str_and_init(&url, &opts)?;
@@ -83,18 +54,27 @@ fn BlogMarkdown(blog_date: sections::BlogDate) -> Element {
         Ok("".to_string())
     });
 
-    match &*markdown.read_unchecked() {
+    let markdown = match &prerendered {
+        Some(html) => Some(Ok(html.clone())),
+        None => markdown.read_unchecked().clone(),
+    };
+
+    match &markdown {
         Some(Ok(response)) => rsx! {
-            div { class: "container min-w-full px-0 mx-0 py-10",
-                div { class: "w-full py-10 px-10 md:mx-auto md:py-12 md:px-12 md:max-w-4xl md:rounded-lg bg-secondary",
-                    div { class: "w-full *:xl:gap-8",
-                        div {
-                            class: "prose prose-invert max-w-full",
-                            dangerous_inner_html: "{response}",
+            div { class: "blog-layout",
+                toc::TableOfContents { toc: entries::get_blog(blog_id).toc }
+                div { class: "blog-content container min-w-full px-0 mx-0 py-10",
+                    div { class: "w-full py-10 px-10 md:mx-auto md:py-12 md:px-12 md:max-w-4xl md:rounded-lg bg-secondary",
+                        div { class: "w-full *:xl:gap-8",
+                            div { class: "prose prose-invert max-w-full",
+                                shortcodes::PostHtml {
+                                    html: response.clone(),
+                                    blog_entry: entries::get_blog(blog_id),
+                                }
+                            }
                         }
                     }
                 }
-            
             }
         },
         Some(Err(_)) => rsx! {
@@ -107,30 +87,8 @@ fn BlogMarkdown(blog_date: sections::BlogDate) -> Element {
 }
 
 #[component]
-fn BlogEntry(blog_date: sections::BlogDate) -> Element {
-    let blog_entry = entries::get_blog(blog_date);
-
-    #[allow(unused_mut)]
-    let mut errorMessage = use_signal(|| Option::<String>::None);
-
-    #[allow(unused_variables)]
-    let mountedFn = move |event: dioxus::prelude::Event<MountedData>| async move {
-        info!("Canvas mounted");
-        #[cfg(feature = "web")]
-        {
-            let element = event.as_web_event().clone().id();
-            let result = run(
-                "/assets/wasm/triangle-demo/triangle_demo.js".into(),
-                element,
-            )
-            .await
-            .err();
-            if let Some(result) = &result {
-                warn!("Error: {}", result);
-                errorMessage.set(Some(format!("{}", result)));
-            }
-        }
-    };
+fn BlogEntry(blog_id: sections::BlogId) -> Element {
+    let blog_entry = entries::get_blog(blog_id);
 
     rsx! {
         div { class: "bg-primary min-h-screen", id: "blog-post",
@@ -147,21 +105,10 @@ fn BlogEntry(blog_date: sections::BlogDate) -> Element {
                     }
                 }
             }
-            if let Some(msg) = errorMessage() {
-                p { class: "text-center text-error", {msg} }
-                p { class: "text-center text-white-text",
-                    "I'm currently running an experiment with WebGPU. This may not work on all browsers."
-                }
-            } else {
-                canvas {
-                    onmounted: mountedFn,
-                    id: "render-canvas",
-                    class: "w-full h-full",
-                    width: 1000,
-                    height: 1000,
-                }
+            for demo in blog_entry.unplaced_demos() {
+                demo::WasmDemo { key: "{demo.canvas_id}", demo }
             }
-            BlogMarkdown { blog_date }
+            BlogMarkdown { blog_id }
         }
     }
 }
@@ -175,7 +122,7 @@ fn change_section(section: sections::ActiveSection) {
 
 #[component]
 fn BlogCard(blog_entry: &'static entries::BlogEntry) -> Element {
-    let href = format!("/blog/{}/index.html", blog_entry.blog_date);
+    let href = format!("/blog/{}/index.html", blog_entry.blog_id);
     let image_thumbnail = &blog_entry.image_file_thumbnail;
     let title = &blog_entry.title;
     let description = &blog_entry.description;
@@ -185,7 +132,7 @@ fn BlogCard(blog_entry: &'static entries::BlogEntry) -> Element {
             class: "shadow",
             onclick: move |event| {
                 event.prevent_default();
-                change_section(sections::ActiveSection::Blog(Some(blog_entry.blog_date)));
+                change_section(sections::ActiveSection::Blog(Some(blog_entry.blog_id)));
             },
             div {
                 style: "background-image: url({image_thumbnail})",
@@ -198,6 +145,13 @@ fn BlogCard(blog_entry: &'static entries::BlogEntry) -> Element {
             div { class: "bg-white py-6 px-5 xl:py-8",
                 span { class: "block font-body text-lg font-semibold text-black", {title} }
                 span { class: "block pt-2 font-body text-grey-20", {description} }
+                div { class: "flex flex-wrap gap-2 pt-3",
+                    for tag in blog_entry.tags {
+                        span { class: "rounded-full bg-grey-50 px-3 py-1 font-body text-xs uppercase text-grey-10",
+                            {tag}
+                        }
+                    }
+                }
             }
         }
     }
@@ -207,7 +161,13 @@ fn BlogCard(blog_entry: &'static entries::BlogEntry) -> Element {
 fn BlogMenu() -> Element {
     let posts_entries = entries::get_blogs();
 
-    let posts = posts_entries.iter().map(|(_, blog_entry)| {
+    // Newest first, in a stable order so pre-rendered pages hydrate cleanly
+    let mut posts_entries = posts_entries.values().collect::<Vec<_>>();
+    posts_entries.sort_by_key(|blog_entry| {
+        std::cmp::Reverse((blog_entry.blog_id.date, blog_entry.blog_id.slug))
+    });
+
+    let posts = posts_entries.into_iter().map(|blog_entry| {
         rsx! {
             BlogCard { blog_entry }
         }
@@ -228,12 +188,12 @@ fn BlogMenu() -> Element {
 }
 
 #[component]
-pub fn Blog(blog_date: Option<sections::BlogDate>) -> Element {
+pub fn Blog(blog_id: Option<sections::BlogId>) -> Element {
     info!("Creating blog");
 
-    if let Some(blog_date) = blog_date {
+    if let Some(blog_id) = blog_id {
         rsx! {
-            BlogEntry { blog_date }
+            BlogEntry { blog_id }
         }
     } else {
         rsx! {
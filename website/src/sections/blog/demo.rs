@@ -0,0 +1,262 @@
+#[cfg(feature = "web")]
+use dioxus::logger::tracing::*;
+use dioxus::prelude::*;
+#[cfg(feature = "web")]
+use wasm_bindgen::prelude::*;
+#[cfg(feature = "web")]
+use web_sys::wasm_bindgen::JsCast;
+
+#[cfg(feature = "web")]
+use dioxus_web::WebEventExt;
+
+#[cfg(feature = "web")]
+use crate::utils::timer::sleep;
+#[cfg(feature = "web")]
+use crate::utils::wasm_loader::{DemoHandle, FrameStats, WasmModule};
+
+use super::entries::{AspectRatio, Demo};
+
+/// Watches for a canvas to get close to the viewport, until it's dropped.
+#[cfg(feature = "web")]
+struct VisibilityWatcher {
+    observer: web_sys::IntersectionObserver,
+    _on_intersection: Closure<dyn FnMut(web_sys::js_sys::Array)>,
+}
+
+#[cfg(feature = "web")]
+impl Drop for VisibilityWatcher {
+    fn drop(&mut self) {
+        self.observer.disconnect();
+    }
+}
+
+/// Resolves once `element` gets close to the viewport. The observer is kept
+/// in `watcher` while waiting, so the component can stop it if it goes away
+/// first.
+#[cfg(feature = "web")]
+async fn wait_until_visible(
+    element: &web_sys::Element,
+    watcher: &std::cell::RefCell<Option<VisibilityWatcher>>,
+) -> dioxus::Result<()> {
+    use web_sys::js_sys;
+
+    let mut observe = |resolve: js_sys::Function, reject: js_sys::Function| {
+        let on_intersection =
+            Closure::<dyn FnMut(js_sys::Array)>::new(move |entries: js_sys::Array| {
+                let visible = entries.iter().any(|entry| {
+                    entry
+                        .unchecked_into::<web_sys::IntersectionObserverEntry>()
+                        .is_intersecting()
+                });
+                if visible {
+                    let _ = resolve.call0(&JsValue::NULL);
+                }
+            });
+
+        // Start loading a bit before the canvas is on screen
+        let options = web_sys::IntersectionObserverInit::new();
+        options.set_root_margin("200px");
+        match web_sys::IntersectionObserver::new_with_options(
+            on_intersection.as_ref().unchecked_ref(),
+            &options,
+        ) {
+            Ok(observer) => {
+                observer.observe(element);
+                *watcher.borrow_mut() = Some(VisibilityWatcher {
+                    observer,
+                    _on_intersection: on_intersection,
+                });
+            }
+            Err(err) => {
+                let _ = reject.call1(&JsValue::NULL, &err);
+            }
+        }
+    };
+
+    let visible = wasm_bindgen_futures::JsFuture::from(js_sys::Promise::new(&mut observe)).await;
+    watcher.borrow_mut().take();
+    visible.map_err(|err| dioxus::CapturedError::from_display(format!("{:#?}", err)))?;
+    Ok(())
+}
+
+/// How often the stats overlay refreshes, in milliseconds.
+#[cfg(feature = "web")]
+const STATS_INTERVAL: i32 = 500;
+
+/// The lines of the stats overlay.
+#[cfg(feature = "web")]
+fn describe_stats(stats: &FrameStats) -> Vec<String> {
+    vec![
+        format!("{:.0} FPS", stats.fps),
+        format!("Frame {:.2} ms", stats.frame_time),
+        match stats.gpu_time {
+            Some(gpu_time) => format!("GPU {gpu_time:.2} ms"),
+            None => "GPU time not supported".to_string(),
+        },
+    ]
+}
+
+#[cfg(feature = "web")]
+impl Demo {
+    /// The JS glue wasm-pack generates for the module.
+    fn script(&self) -> String {
+        WasmModule::script_url(self.module)
+    }
+}
+
+/// The canvas a demo renders to, as wide as its container and as high as
+/// `aspect_ratio` makes it. The demo sizes its pixels to it.
+#[component]
+pub fn DemoCanvas(
+    id: &'static str,
+    aspect_ratio: AspectRatio,
+    onmounted: EventHandler<dioxus::prelude::Event<MountedData>>,
+) -> Element {
+    rsx! {
+        canvas {
+            onmounted: move |event| onmounted.call(event),
+            id,
+            class: "w-full",
+            style: "aspect-ratio: {aspect_ratio}",
+        }
+    }
+}
+
+/// A canvas running one of the demos of a post. The module is only fetched
+/// once the canvas scrolls into view, and the demo is stopped when the
+/// component goes away. Once it runs, its frame times can be shown over it.
+#[component]
+pub fn WasmDemo(demo: &'static Demo) -> Element {
+    #[allow(unused_mut)]
+    let mut errorMessage = use_signal(|| Option::<String>::None);
+    #[allow(unused_mut)]
+    let mut backend = use_signal(|| Option::<String>::None);
+    let mut show_stats = use_signal(|| false);
+    #[allow(unused_mut)]
+    let mut stats = use_signal(Vec::<String>::new);
+
+    // Not a signal, as those are already gone when `use_drop` runs
+    #[cfg(feature = "web")]
+    let handle = use_hook(|| std::rc::Rc::new(std::cell::RefCell::new(None::<DemoHandle>)));
+    #[cfg(feature = "web")]
+    let watcher = use_hook(|| std::rc::Rc::new(std::cell::RefCell::new(None::<VisibilityWatcher>)));
+    #[cfg(feature = "web")]
+    use_drop({
+        let handle = handle.clone();
+        let watcher = watcher.clone();
+        move || {
+            watcher.borrow_mut().take();
+            if let Some(handle) = handle.borrow_mut().take() {
+                info!("Stopping demo {}", demo.canvas_id);
+                if let Err(err) = handle.stop() {
+                    warn!("Error: {}", err);
+                }
+            }
+        }
+    });
+
+    #[cfg(feature = "web")]
+    use_future({
+        let handle = handle.clone();
+        move || {
+            let handle = handle.clone();
+            async move {
+                loop {
+                    sleep(STATS_INTERVAL).await;
+                    if !*show_stats.peek() {
+                        continue;
+                    }
+                    let latest = handle.borrow().as_ref().and_then(DemoHandle::stats);
+                    stats.set(latest.as_ref().map(describe_stats).unwrap_or_default());
+                }
+            }
+        }
+    });
+
+    #[allow(unused_variables)]
+    let mountedFn = move |event: dioxus::prelude::Event<MountedData>| {
+        #[cfg(feature = "web")]
+        let (handle, watcher) = (handle.clone(), watcher.clone());
+        async move {
+            #[cfg(feature = "web")]
+            {
+                let element = event.as_web_event();
+                let result = async {
+                    wait_until_visible(&element, &watcher).await?;
+                    info!("Starting demo {}", demo.canvas_id);
+                    let module = WasmModule::load(&demo.script()).await?;
+                    let running = module.run(demo.canvas_id, demo.scene).await?;
+                    if demo.max_resolution.is_some() {
+                        running.set_max_resolution(demo.max_resolution)?;
+                    }
+                    backend.set(running.backend());
+                    *handle.borrow_mut() = Some(running);
+                    dioxus::Result::Ok(())
+                }
+                .await
+                .err();
+                if let Some(result) = &result {
+                    warn!("Error: {}", result);
+                    errorMessage.set(Some(format!("{}", result)));
+                }
+            }
+        }
+    };
+
+    rsx! {
+        if let Some(msg) = errorMessage() {
+            div { class: "py-4",
+                if let Some(image) = demo.fallback_image {
+                    img {
+                        class: "w-full",
+                        src: image,
+                        alt: demo.fallback_text.unwrap_or("Demo preview"),
+                    }
+                }
+                p { class: "text-center text-white-text",
+                    {
+                        demo.fallback_text
+                            .unwrap_or(
+                                "I'm currently running an experiment with WebGPU. This may not work on all browsers.",
+                            )
+                    }
+                }
+                p { class: "text-center text-error", {msg} }
+            }
+        } else {
+            div { class: "relative",
+                DemoCanvas {
+                    id: demo.canvas_id,
+                    aspect_ratio: demo.aspect_ratio,
+                    onmounted: mountedFn,
+                }
+                if show_stats() {
+                    div { class: "demo-stats", aria_live: "off",
+                        if stats.read().is_empty() {
+                            div { "Measuring…" }
+                        }
+                        for line in stats.read().iter() {
+                            div { "{line}" }
+                        }
+                    }
+                }
+            }
+            if let Some(backend) = backend() {
+                p { class: "text-center text-sm text-grey-20 pt-2",
+                    "Rendered with {backend} · "
+                    button {
+                        r#type: "button",
+                        class: "demo-stats-toggle",
+                        aria_pressed: "{show_stats}",
+                        onclick: move |_| show_stats.toggle(),
+                        if show_stats() {
+                            "Hide stats"
+                        } else {
+                            "Show stats"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
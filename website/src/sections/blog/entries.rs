@@ -2,11 +2,73 @@ use std::{collections::HashMap, sync::LazyLock};
 
 use manganis::*;
 
+/// A heading of a post, linking to the anchor `build.rs` gave it.
+#[derive(Clone, PartialEq)]
+pub struct TocEntry {
+    pub level: u8,
+    pub id: &'static str,
+    pub title: &'static str,
+}
+
+/// How wide a demo canvas is for its height, such as 16 / 9.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AspectRatio {
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Formatted as a CSS `aspect-ratio`.
+impl std::fmt::Display for AspectRatio {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} / {}", self.width, self.height)
+    }
+}
+
+/// An interactive wasm module declared in the front matter of a post.
+#[derive(Clone, PartialEq)]
+pub struct Demo {
+    pub module: &'static str,
+    pub canvas_id: &'static str,
+    /// The scene of the module to run, or its default one.
+    pub scene: Option<&'static str>,
+    pub aspect_ratio: AspectRatio,
+    /// Caps the longest side of the canvas, in device pixels, or the default
+    /// of the module when `None`.
+    pub max_resolution: Option<u32>,
+    pub fallback_image: Option<Asset>,
+    pub fallback_text: Option<&'static str>,
+}
+
+/// An embed a post asks for with a `{{name ...}}` line, in the order they
+/// appear in its HTML.
+// Not every kind is used by the current posts
+#[allow(dead_code)]
+#[derive(Clone, PartialEq)]
+pub enum Shortcode {
+    /// Places `demos[demo]` of the post.
+    WasmDemo {
+        demo: usize,
+    },
+    Youtube {
+        id: &'static str,
+    },
+    Figure {
+        src: &'static str,
+        caption: &'static str,
+    },
+    /// The contents of notes are part of the HTML of the post.
+    Note,
+}
+
 #[derive(Clone, PartialEq)]
 pub struct BlogEntry {
-    pub blog_date: crate::sections::BlogDate,
+    pub blog_id: crate::sections::BlogId,
     pub title: &'static str,
     pub description: &'static str,
+    pub tags: &'static [&'static str],
+    pub toc: &'static [TocEntry],
+    pub demos: &'static [Demo],
+    pub shortcodes: &'static [Shortcode],
     pub image_file_thumbnail: Asset,
     pub image_file_blog: Asset,
     pub html: Asset,
@@ -14,17 +76,51 @@ pub struct BlogEntry {
 
 include!(concat!(env!("OUT_DIR"), "/current_blogs.rs"));
 
-pub fn get_blogs() -> &'static HashMap<crate::sections::BlogDate, BlogEntry> {
-    static MAP_BLOGS: LazyLock<HashMap<crate::sections::BlogDate, BlogEntry>> =
-        LazyLock::new(|| {
-            BLOGS
-                .into_iter()
-                .map(|blog| (blog.blog_date, blog))
-                .collect()
-        });
+impl BlogEntry {
+    /// The demos that aren't placed with a shortcode, which go above the post.
+    pub fn unplaced_demos(&self) -> impl Iterator<Item = &Demo> {
+        self.demos.iter().enumerate().filter_map(|(index, demo)| {
+            (!self
+                .shortcodes
+                .contains(&Shortcode::WasmDemo { demo: index }))
+            .then_some(demo)
+        })
+    }
+}
+
+pub fn get_blogs() -> &'static HashMap<crate::sections::BlogId, BlogEntry> {
+    static MAP_BLOGS: LazyLock<HashMap<crate::sections::BlogId, BlogEntry>> =
+        LazyLock::new(|| BLOGS.into_iter().map(|blog| (blog.blog_id, blog)).collect());
     &MAP_BLOGS
 }
 
-pub fn get_blog(date: crate::sections::BlogDate) -> &'static BlogEntry {
-    get_blogs().get(&date).unwrap()
+pub fn get_blog(id: crate::sections::BlogId) -> &'static BlogEntry {
+    get_blogs().get(&id).unwrap()
+}
+
+/// Where `build.rs` wrote the HTML of a post. Only the pre-renderer reads it
+/// from disk, the site fetches it.
+#[cfg(feature = "generate_htmls")]
+pub fn html_source_path(id: crate::sections::BlogId) -> std::path::PathBuf {
+    std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("assets/blogs")
+        .join(id.date.iso_date())
+        .join(id.slug)
+        .join("index.html")
+}
+
+pub fn find_blog(date: crate::sections::BlogDate, slug: &str) -> Option<&'static BlogEntry> {
+    get_blogs()
+        .values()
+        .find(|blog| blog.blog_id.date == date && blog.blog_id.slug == slug)
+}
+
+/// Posts are generated sorted by directory name, so the first one listed for a
+/// date is the one without a slug in its directory name, or else the one whose
+/// slug sorts first.
+pub fn first_blog_of_day(date: crate::sections::BlogDate) -> Option<&'static BlogEntry> {
+    BLOGS
+        .iter()
+        .find(|blog| blog.blog_id.date == date)
+        .and_then(|blog| get_blogs().get(&blog.blog_id))
 }
@@ -0,0 +1,92 @@
+use dioxus::prelude::*;
+
+use super::demo::WasmDemo;
+use super::entries::{BlogEntry, Shortcode};
+
+/// A piece of the HTML of a post, split at the shortcode placeholders.
+enum Segment<'a> {
+    Html(&'a str),
+    Shortcode { index: usize, fallback: &'a str },
+}
+
+/// Splits `html` at the `<!--shortcode:N-->fallback<!--/shortcode-->`
+/// placeholders `build.rs` leaves in it.
+fn split_shortcodes(html: &str) -> Vec<Segment<'_>> {
+    const START: &str = "<!--shortcode:";
+    const END: &str = "<!--/shortcode-->";
+
+    let mut segments = vec![];
+    let mut rest = html;
+    while let Some(start) = rest.find(START) {
+        let Some((index, after)) = rest[start + START.len()..].split_once("-->") else {
+            break;
+        };
+        let (Ok(index), Some((fallback, after))) = (index.parse(), after.split_once(END)) else {
+            break;
+        };
+        segments.push(Segment::Html(&rest[..start]));
+        segments.push(Segment::Shortcode { index, fallback });
+        rest = after;
+    }
+    segments.push(Segment::Html(rest));
+    segments
+}
+
+/// Renders the HTML of a post, replacing its shortcodes with their components.
+#[component]
+pub fn PostHtml(html: String, blog_entry: &'static BlogEntry) -> Element {
+    let segments = split_shortcodes(&html)
+        .into_iter()
+        .enumerate()
+        .map(|(position, segment)| match segment {
+            Segment::Html(html) => rsx! {
+                div { key: "{position}", dangerous_inner_html: "{html}" }
+            },
+            Segment::Shortcode { index, fallback } => match blog_entry.shortcodes.get(index) {
+                Some(Shortcode::WasmDemo { demo }) => rsx! {
+                    WasmDemo { key: "{position}", demo: &blog_entry.demos[*demo] }
+                },
+                Some(Shortcode::Youtube { id }) => rsx! {
+                    Youtube { key: "{position}", id }
+                },
+                Some(Shortcode::Figure { src, caption }) => rsx! {
+                    Figure { key: "{position}", src, caption }
+                },
+                Some(Shortcode::Note) => rsx! {
+                    aside { key: "{position}", class: "blog-note", dangerous_inner_html: "{fallback}" }
+                },
+                None => rsx! {
+                    div { key: "{position}", dangerous_inner_html: "{fallback}" }
+                },
+            },
+        });
+
+    rsx! {
+        {segments}
+    }
+}
+
+#[component]
+fn Youtube(id: &'static str) -> Element {
+    rsx! {
+        div { class: "blog-embed",
+            iframe {
+                src: "https://www.youtube-nocookie.com/embed/{id}",
+                title: "YouTube video",
+                "loading": "lazy",
+                allow: "accelerometer; autoplay; clipboard-write; encrypted-media; gyroscope; picture-in-picture",
+                allowfullscreen: true,
+            }
+        }
+    }
+}
+
+#[component]
+fn Figure(src: &'static str, caption: &'static str) -> Element {
+    rsx! {
+        figure {
+            img { src, alt: caption, loading: "lazy" }
+            figcaption { {caption} }
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use dioxus::prelude::*;
+
+use super::entries::TocEntry;
+
+/// The headings of a post, as a sidebar on wide screens and as a collapsible
+/// block above the post on narrow ones.
+#[component]
+pub fn TableOfContents(toc: &'static [TocEntry]) -> Element {
+    if toc.is_empty() {
+        return rsx! {};
+    }
+
+    rsx! {
+        nav { class: "blog-toc", aria_label: "Table of contents",
+            details { class: "blog-toc-collapsible bg-secondary",
+                summary { class: "font-header font-semibold uppercase text-white-text",
+                    "Contents"
+                }
+                TocList { toc }
+            }
+            div { class: "blog-toc-sidebar",
+                p { class: "font-header font-semibold uppercase text-white-text", "Contents" }
+                TocList { toc }
+            }
+        }
+    }
+}
+
+#[component]
+fn TocList(toc: &'static [TocEntry]) -> Element {
+    // Indent relative to the top level used by the post
+    let top_level = toc.iter().map(|entry| entry.level).min().unwrap_or(1);
+
+    rsx! {
+        ul { class: "mt-2",
+            for entry in toc {
+                li {
+                    class: "py-1 font-body text-sm",
+                    style: "padding-left: {entry.level - top_level}rem",
+                    a { class: "text-white-text", href: "#{entry.id}", {entry.title} }
+                }
+            }
+        }
+    }
+}
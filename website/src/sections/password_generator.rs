@@ -1,19 +1,92 @@
+use std::borrow::Cow;
+use std::rc::Rc;
+
 use dioxus::logger::tracing::*;
 use dioxus::prelude::*;
-use rand::Rng;
+use serde::{Deserialize, Serialize};
 
+use crate::components::form::checkbox::Checkbox;
+use crate::components::form::input_number::InputNumber;
 use crate::components::form::input_text::InputText;
 use crate::components::form::range::Range;
 use crate::components::form::read_only_input_text::ReadOnlyInputText;
 use crate::components::form::select::Select;
 
-use rand::distributions::{Alphanumeric, DistString};
+mod alphabet;
+mod bulk;
+mod derive;
+mod passphrase;
+mod policy;
+mod presets;
+mod strength;
+
+use bulk::Format;
+use derive::DeriveError;
+use passphrase::{Capitalization, PassphraseOptions};
+use policy::{FirstCharacter, Policy, PolicyError};
+use presets::{Preset, NO_PRESET};
+use strength::Strength;
+
+const DEFAULT_MODE: Mode = Mode::Characters;
+const DEFAULT_DICTIONARY: Dictionary = Dictionary::Ascii;
+const DEFAULT_SIZE: i64 = 32;
+const MAX_SIZE: i64 = 64;
+const DEFAULT_CAPITALIZATION: Capitalization = Capitalization::Lowercase;
+const DEFAULT_WORDS: i64 = 6;
+const MAX_WORDS: i64 = 16;
+const DEFAULT_SEPARATOR: &str = "-";
+const DEFAULT_FIRST: FirstCharacter = FirstCharacter::Any;
+const MAX_CLASS_COUNT: i64 = 8;
+const DEFAULT_BULK_COUNT: i64 = 20;
+const MAX_BULK_COUNT: i64 = 5000;
+/// Bulk passwords generated between updates of the page, so it stays
+/// responsive.
+const BULK_CHUNK: usize = 100;
+/// Height of each row of bulk passwords, in pixels.
+const BULK_ROW_HEIGHT: usize = 40;
+/// Rows of bulk passwords in view at once.
+const BULK_VISIBLE_ROWS: usize = 10;
+/// Rows rendered above and below the ones in view, so fast scrolling doesn't
+/// show blank space.
+const BULK_OVERSCAN_ROWS: usize = 5;
+const MAX_SITE_COUNTER: i64 = 9999;
+
+/// What the generator makes.
+#[derive(Debug, Clone, Copy, PartialEq, enum_iterator::Sequence, Serialize, Deserialize)]
+enum Mode {
+    /// Random characters from a [`Dictionary`].
+    Characters,
+    /// Random words, easier to remember.
+    Passphrase,
+}
+
+impl From<&str> for Mode {
+    fn from(value: &str) -> Self {
+        match value {
+            "Characters" => Mode::Characters,
+            "Passphrase" => Mode::Passphrase,
+            _ => Mode::Characters,
+        }
+    }
+}
+
+impl From<Mode> for String {
+    fn from(value: Mode) -> Self {
+        match value {
+            Mode::Characters => "Characters".to_string(),
+            Mode::Passphrase => "Passphrase".to_string(),
+        }
+    }
+}
 
-#[derive(Debug, Clone, Copy, PartialEq, enum_iterator::Sequence)]
+// Serialized as they are shown
+#[derive(Debug, Clone, Copy, PartialEq, enum_iterator::Sequence, Serialize, Deserialize)]
 enum Dictionary {
     Readable,
     Alphanumeric,
+    #[serde(rename = "ASCII")]
     Ascii,
+    #[serde(rename = "Spanish Keyboard")]
     SpanishKeyboard,
     Unicode,
     Custom,
@@ -54,55 +127,91 @@ fn get_unicode_password(number_of_characters: i64) -> String {
     my_str.to_string()
 }
 
-fn get_alphanumeric_password(number_of_characters: i64) -> String {
-    Alphanumeric.sample_string(
-        &mut rand::thread_rng(),
+fn get_custom_password(
+    custom_dictionary: &str,
+    number_of_characters: i64,
+    policy: &Policy,
+) -> Result<String, PolicyError> {
+    policy.generate(
+        custom_dictionary,
         number_of_characters.try_into().unwrap(),
+        &mut rand::thread_rng(),
     )
 }
 
-fn get_custom_password(custom_dictionary: &str, number_of_characters: i64) -> String {
-    if custom_dictionary.is_empty() {
-        return "".to_string();
-    }
-    let custom_dictionary = custom_dictionary.chars().collect::<Vec<char>>();
-    let mut rng = rand::thread_rng();
-    let one_char = || custom_dictionary[rng.gen_range(0..custom_dictionary.len())];
-    std::iter::repeat_with(one_char)
-        .take(number_of_characters.try_into().unwrap())
-        .collect()
-}
-
+const ALPHANUMERIC_DICTIONARY: &str =
+    "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
 const ASCII_DICTIONARY: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ1234567890!\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~";
 const READABLE_DICTIONARY: &str = "abcdefghijkmnopqrstuvwxyzABCDEFGHJKLMNPQRSTUVWXYZ23456789";
 const SPANISH_DICTIONARY: &str = "qwertyuiopasdfghjklñçzxcvbnm1234567890QWERTYUIOPASDFGHJKLÑÇZXCVBNM,.;:-_^*+[]{}!|\\\"@·#$%&/()=?¿?'¡àèìòùÀÈÌÒÙÁÉÍÓÚáéíóú<>";
 
-fn get_password(
-    dictionary: Dictionary,
-    custom_dictionary: &str,
-    number_of_characters: i64,
-) -> String {
-    match dictionary {
-        Dictionary::Alphanumeric => get_alphanumeric_password(number_of_characters),
-        Dictionary::Ascii => get_custom_password(ASCII_DICTIONARY, number_of_characters),
-        Dictionary::Readable => get_custom_password(READABLE_DICTIONARY, number_of_characters),
-        Dictionary::SpanishKeyboard => {
-            get_custom_password(SPANISH_DICTIONARY, number_of_characters)
+/// Valid `char`s, which the Unicode dictionary picks most non-ASCII ones from.
+const UNICODE_SCALAR_VALUES: f64 = (0x110000 - 0x800) as f64;
+/// ASCII characters but NUL, which the Unicode dictionary gets from random
+/// bytes.
+const ASCII_VALUES: f64 = 127.0;
+
+/// The characters the dictionary of `preset` picks from, or `None` for the
+/// Unicode one, which doesn't pick from a fixed set. Custom dictionaries are
+/// normalized, while the others may repeat characters, which
+/// [`Policy::alphabet`] leaves out.
+fn dictionary_characters(preset: &Preset) -> Option<Cow<'static, str>> {
+    match preset.dictionary {
+        Dictionary::Alphanumeric => Some(ALPHANUMERIC_DICTIONARY.into()),
+        Dictionary::Ascii => Some(ASCII_DICTIONARY.into()),
+        Dictionary::Readable => Some(READABLE_DICTIONARY.into()),
+        Dictionary::SpanishKeyboard => Some(SPANISH_DICTIONARY.into()),
+        Dictionary::Unicode => None,
+        Dictionary::Custom => {
+            Some(alphabet::normalize(&preset.custom_dictionary, preset.normalize_unicode).into())
         }
-        Dictionary::Unicode => get_unicode_password(number_of_characters),
-        Dictionary::Custom => get_custom_password(custom_dictionary, number_of_characters),
+    }
+}
+
+/// Bits of entropy of `password`, as generated with `preset`. With
+/// requirements it's an upper bound, as they rule some passwords out.
+fn password_entropy(preset: &Preset, password: &str) -> f64 {
+    match dictionary_characters(preset) {
+        Some(characters) => {
+            let size = preset.policy.alphabet(&characters).len();
+            password.chars().count() as f64 * (size as f64).log2()
+        }
+        // An estimate, as `rand_utf8` mixes random bytes and code points
+        None => password
+            .chars()
+            .map(|c| {
+                if c.is_ascii() {
+                    ASCII_VALUES.log2()
+                } else {
+                    UNICODE_SCALAR_VALUES.log2()
+                }
+            })
+            .sum(),
+    }
+}
+
+/// A password or passphrase with the settings of `preset`. The Unicode
+/// dictionary ignores the requirements.
+fn generate(preset: &Preset) -> Result<String, PolicyError> {
+    let length = preset.length as i64;
+    match preset.mode {
+        Mode::Characters => match dictionary_characters(preset) {
+            Some(characters) => get_custom_password(&characters, length, &preset.policy),
+            None => Ok(get_unicode_password(length)),
+        },
+        Mode::Passphrase => Ok(preset.passphrase.generate(&mut rand::thread_rng())),
     }
 }
 
 #[component]
-pub fn CopyButton(text: Resource<String>) -> Element {
+pub fn CopyButton(text: ReadOnlySignal<String>) -> Element {
     rsx! {
         div {
             button {
                 r#type: "button",
                 onclick: move |event| async move {
                     event.prevent_default();
-                    let password = text().unwrap_or_default();
+                    let password = text();
                     info!("Copying to clipboard: {}", password);
                     crate::utils::clipboard::set_clipboard(password).await;
                 },
@@ -113,40 +222,709 @@ pub fn CopyButton(text: Resource<String>) -> Element {
     }
 }
 
+/// The requirements of [`Policy`], for the character sets it applies to.
+#[component]
+fn PolicyOptions(
+    min_lowercase: Signal<i64>,
+    min_uppercase: Signal<i64>,
+    min_digits: Signal<i64>,
+    min_symbols: Signal<i64>,
+    max_count: i64,
+    excluded: Signal<String>,
+    no_repeats: Signal<bool>,
+    no_sequences: Signal<bool>,
+    first: Signal<String>,
+    default_first: String,
+) -> Element {
+    let counts = [
+        (
+            "min-lowercase",
+            "At least this many lowercase letters",
+            min_lowercase,
+        ),
+        (
+            "min-uppercase",
+            "At least this many uppercase letters",
+            min_uppercase,
+        ),
+        ("min-digits", "At least this many digits", min_digits),
+        ("min-symbols", "At least this many symbols", min_symbols),
+    ];
+
+    rsx! {
+        div { class: "grid grid-cols-1 gap-6 mt-4 sm:grid-cols-2",
+            for (id, label, source) in counts {
+                div { key: "{id}",
+                    label { r#for: id, class: "text-primary dark:text-gray-200", "{label}" }
+                    Range {
+                        source,
+                        min: 0,
+                        max: max_count,
+                        step: 1,
+                        id,
+                    }
+                }
+            }
+            div {
+                label {
+                    r#for: "excluded-characters",
+                    class: "text-primary dark:text-gray-200",
+                    "Excluded characters"
+                }
+                InputText {
+                    source: excluded,
+                    placeholder: "eg. O0Il1",
+                    id: "excluded-characters",
+                }
+            }
+            div {
+                label {
+                    r#for: "select-first-character",
+                    class: "text-primary dark:text-gray-200",
+                    "First character"
+                }
+                Select {
+                    source: first,
+                    default: default_first,
+                    values: enum_iterator::all::<FirstCharacter>().map(|x| x.into()).collect(),
+                    id: "select-first-character",
+                }
+            }
+            Checkbox {
+                source: no_repeats,
+                label: "No repeated characters, like aaa",
+                id: "no-repeats",
+            }
+            Checkbox {
+                source: no_sequences,
+                label: "No sequences, like abc or 321",
+                id: "no-sequences",
+            }
+        }
+    }
+}
+
+/// Saves the settings as named presets, and shares them as JSON.
+#[component]
+fn PresetOptions(
+    saved: Signal<Vec<Preset>>,
+    selected: Signal<String>,
+    current: ReadOnlySignal<Preset>,
+    message: Signal<Option<Result<String, String>>>,
+) -> Element {
+    const BUTTON_CLASS: &str = "px-6 py-2 w-full leading-5 text-white-text transition-colors duration-200 transform bg-tertiary rounded-md hover:bg-quaternary focus:outline-none focus:bg-gray-600";
+
+    let name = use_signal(String::new);
+    let mut json = use_signal(String::new);
+    let names = std::iter::once(NO_PRESET.to_string())
+        .chain(saved.read().iter().map(|preset| preset.name.clone()))
+        .collect::<Vec<_>>();
+
+    rsx! {
+        div { class: "grid grid-cols-1 gap-6 mt-4 sm:grid-cols-2",
+            div {
+                label {
+                    r#for: "select-preset",
+                    class: "text-primary dark:text-gray-200",
+                    "Preset"
+                }
+                Select {
+                    source: selected,
+                    default: NO_PRESET.to_string(),
+                    values: names,
+                    id: "select-preset",
+                }
+            }
+            div {
+                label {
+                    r#for: "preset-name",
+                    class: "text-primary dark:text-gray-200",
+                    "Save the settings as"
+                }
+                InputText {
+                    source: name,
+                    placeholder: "eg. Team defaults",
+                    id: "preset-name",
+                }
+            }
+        }
+        div { class: "grid grid-cols-2 gap-6 mt-4",
+            button {
+                r#type: "button",
+                onclick: move |event| {
+                    event.prevent_default();
+                    let preset = Preset {
+                        name: name().trim().to_string(),
+                        ..current()
+                    };
+                    if let Err(err) = preset.validate() {
+                        message.set(Some(Err(err.to_string())));
+                        return;
+                    }
+                    let preset_name = preset.name.clone();
+                    presets::merge(&mut saved.write(), [preset]);
+                    presets::store(&saved.read());
+                    selected.set(preset_name.clone());
+                    message.set(Some(Ok(format!("Saved \"{preset_name}\""))));
+                },
+                class: BUTTON_CLASS,
+                "Save preset"
+            }
+            button {
+                r#type: "button",
+                disabled: selected() == NO_PRESET,
+                onclick: move |event| {
+                    event.prevent_default();
+                    let preset_name = selected();
+                    saved.write().retain(|preset| preset.name != preset_name);
+                    presets::store(&saved.read());
+                    selected.set(NO_PRESET.to_string());
+                    message.set(Some(Ok(format!("Deleted \"{preset_name}\""))));
+                },
+                class: BUTTON_CLASS,
+                "Delete preset"
+            }
+        }
+        div { class: "grid grid-cols-1 gap-6 mt-4",
+            div {
+                label {
+                    r#for: "presets-json",
+                    class: "text-primary dark:text-gray-200",
+                    "Presets JSON, to share them or import the ones shared with you"
+                }
+                textarea {
+                    class: "block w-full px-4 py-2 mt-2 text-gray-700 bg-white border border-gray-300 rounded-md dark:bg-gray-800 dark:text-gray-300 dark:border-gray-600 focus:border-blue-500 dark:focus:border-blue-500 focus:outline-none focus:ring",
+                    id: "presets-json",
+                    rows: 6,
+                    spellcheck: false,
+                    value: "{json}",
+                    oninput: move |event| json.set(event.value()),
+                }
+            }
+        }
+        div { class: "grid grid-cols-2 gap-6 mt-4",
+            button {
+                r#type: "button",
+                onclick: move |event| async move {
+                    event.prevent_default();
+                    let exported = presets::to_json(&saved.read());
+                    json.set(exported.clone());
+                    crate::utils::clipboard::set_clipboard(exported).await;
+                    message.set(Some(Ok("Copied the presets to the clipboard".to_string())));
+                },
+                class: BUTTON_CLASS,
+                "Export presets"
+            }
+            button {
+                r#type: "button",
+                onclick: move |event| {
+                    event.prevent_default();
+                    match presets::from_json(&json()) {
+                        Ok(imported) => {
+                            let count = imported.len();
+                            presets::merge(&mut saved.write(), imported);
+                            presets::store(&saved.read());
+                            message.set(Some(Ok(format!("Imported {count} presets"))));
+                        }
+                        Err(err) => message.set(Some(Err(err.to_string()))),
+                    }
+                },
+                class: BUTTON_CLASS,
+                "Import presets"
+            }
+        }
+        match message() {
+            Some(Ok(notice)) => rsx! {
+                p { class: "text-sm text-primary dark:text-gray-200 pt-2", role: "status", "{notice}" }
+            },
+            Some(Err(error)) => rsx! {
+                p { class: "text-sm text-error pt-2", role: "alert", "{error}" }
+            },
+            None => rsx! {},
+        }
+    }
+}
+
+/// Bulk passwords, rendering only the rows in view so thousands of them
+/// scroll smoothly.
+#[component]
+fn PasswordList(passwords: ReadOnlySignal<Vec<String>>) -> Element {
+    let mut list = use_signal(|| None::<Rc<MountedData>>);
+    let mut scroll_top = use_signal(|| 0.0);
+
+    let total = passwords.read().len();
+    let first = (scroll_top() as usize / BULK_ROW_HEIGHT)
+        .saturating_sub(BULK_OVERSCAN_ROWS)
+        .min(total);
+    let last = (first + BULK_VISIBLE_ROWS + 2 * BULK_OVERSCAN_ROWS).min(total);
+    let rows = passwords.read()[first..last]
+        .iter()
+        .cloned()
+        .enumerate()
+        .map(|(offset, password)| (first + offset, password))
+        .collect::<Vec<_>>();
+
+    rsx! {
+        div {
+            class: "bulk-list mt-4",
+            style: "height: {BULK_VISIBLE_ROWS * BULK_ROW_HEIGHT}px",
+            role: "list",
+            aria_label: "Generated passwords",
+            onmounted: move |event: dioxus::prelude::Event<MountedData>| list.set(Some(event.data())),
+            onscroll: move |_| async move {
+                let Some(list) = list() else {
+                    return;
+                };
+                if let Ok(offset) = list.get_scroll_offset().await {
+                    scroll_top.set(offset.y);
+                }
+            },
+            div {
+                class: "bulk-list-content",
+                style: "height: {total * BULK_ROW_HEIGHT}px",
+                for (index, password) in rows {
+                    div {
+                        key: "{index}",
+                        class: "bulk-row",
+                        role: "listitem",
+                        style: "top: {index * BULK_ROW_HEIGHT}px; height: {BULK_ROW_HEIGHT}px",
+                        span { class: "bulk-row-index", "{index + 1}" }
+                        span { class: "bulk-row-password", "{password}" }
+                        button {
+                            r#type: "button",
+                            class: "bulk-row-copy",
+                            aria_label: "Copy password {index + 1}",
+                            onclick: move |event| {
+                                event.prevent_default();
+                                let password = password.clone();
+                                async move {
+                                    crate::utils::clipboard::set_clipboard(password).await;
+                                }
+                            },
+                            "Copy"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Many passwords with the same settings, such as for test accounts, to copy
+/// one by one or download together.
+#[component]
+fn BulkPasswords(settings: ReadOnlySignal<Preset>) -> Element {
+    const BUTTON_CLASS: &str = "px-6 py-2 w-full leading-5 text-white-text transition-colors duration-200 transform bg-tertiary rounded-md hover:bg-quaternary focus:outline-none focus:bg-gray-600";
+
+    let count = use_signal(|| DEFAULT_BULK_COUNT);
+    let format = use_signal(|| Into::<String>::into(Format::Csv));
+    let mut passwords = use_signal(Vec::<String>::new);
+    let mut error = use_signal(|| None::<PolicyError>);
+    // The generation running, and how many of how many passwords it's made
+    let mut task = use_signal(|| None::<Task>);
+    let mut progress = use_signal(|| (0, 0));
+
+    let mut cancel = move || {
+        if let Some(task) = task.take() {
+            task.cancel();
+        }
+    };
+
+    rsx! {
+        div { class: "grid grid-cols-1 gap-6 mt-4 sm:grid-cols-2",
+            div {
+                label {
+                    r#for: "bulk-count",
+                    class: "text-primary dark:text-gray-200",
+                    "Number of passwords"
+                }
+                InputNumber {
+                    source: count,
+                    min: 1,
+                    max: MAX_BULK_COUNT,
+                    id: "bulk-count",
+                }
+            }
+            div {
+                label {
+                    r#for: "select-bulk-format",
+                    class: "text-primary dark:text-gray-200",
+                    "Download as"
+                }
+                Select {
+                    source: format,
+                    default: Into::<String>::into(Format::Csv),
+                    values: enum_iterator::all::<Format>().map(|x| x.into()).collect(),
+                    id: "select-bulk-format",
+                }
+            }
+        }
+        div { class: "grid grid-cols-2 gap-6 mt-4",
+            if task.read().is_some() {
+                button {
+                    r#type: "button",
+                    onclick: move |event| {
+                        event.prevent_default();
+                        cancel();
+                    },
+                    class: BUTTON_CLASS,
+                    "Cancel"
+                }
+            } else {
+                button {
+                    r#type: "button",
+                    onclick: move |event| {
+                        event.prevent_default();
+                        let settings = settings();
+                        let total = count().clamp(1, MAX_BULK_COUNT) as usize;
+                        passwords.set(Vec::new());
+                        error.set(None);
+                        progress.set((0, total));
+                        task.set(Some(spawn(async move {
+                            let mut generated = Vec::with_capacity(total);
+                            while generated.len() < total {
+                                let chunk = BULK_CHUNK.min(total - generated.len());
+                                for _ in 0..chunk {
+                                    match generate(&settings) {
+                                        Ok(password) => generated.push(password),
+                                        Err(err) => {
+                                            error.set(Some(err));
+                                            task.set(None);
+                                            return;
+                                        }
+                                    }
+                                }
+                                progress.set((generated.len(), total));
+                                crate::utils::timer::sleep(0).await;
+                            }
+                            passwords.set(generated);
+                            task.set(None);
+                        })));
+                    },
+                    class: BUTTON_CLASS,
+                    "Generate passwords"
+                }
+            }
+            button {
+                r#type: "button",
+                disabled: passwords.read().is_empty(),
+                onclick: move |event| {
+                    event.prevent_default();
+                    let format = Format::from(format().as_str());
+                    crate::utils::download::download(
+                        format.file_name(),
+                        format.mime_type(),
+                        &format.render(&passwords.read()),
+                    );
+                },
+                class: BUTTON_CLASS,
+                "Download"
+            }
+        }
+        if task.read().is_some() {
+            div { class: "mt-4",
+                progress {
+                    class: "w-full",
+                    max: "{progress().1}",
+                    value: "{progress().0}",
+                    aria_label: "Generating passwords",
+                }
+                p { class: "text-sm text-primary dark:text-gray-200 pt-2", role: "status",
+                    "Generated {progress().0} of {progress().1} passwords"
+                }
+            }
+        }
+        if Format::from(format().as_str()) == Format::Csv {
+            p { class: "text-sm text-primary dark:text-gray-200 pt-2",
+                "Passwords starting with = + - or @ are quoted in the CSV, but some spreadsheets still read them as formulas. Import the password column as text."
+            }
+        }
+        if let Some(error) = error() {
+            p { class: "text-sm text-error pt-2", role: "alert", "{error}" }
+        }
+        if !passwords.read().is_empty() {
+            PasswordList { passwords }
+        }
+    }
+}
+
+/// Derives the password of a site from a master passphrase, with the
+/// character set and length above. Nothing is stored: the same inputs give the
+/// same password again.
+#[component]
+fn SitePassword(settings: ReadOnlySignal<Preset>) -> Element {
+    let mut master = use_signal(String::new);
+    let site = use_signal(String::new);
+    let counter = use_signal(|| 1);
+    let mut derived = use_signal(String::new);
+    let mut error = use_signal(|| None::<DeriveError>);
+
+    // A password shown for other inputs would be misleading
+    use_effect(move || {
+        settings.read();
+        master.read();
+        site.read();
+        counter.read();
+        derived.set(String::new());
+        error.set(None);
+    });
+
+    rsx! {
+        div { class: "grid grid-cols-1 gap-6 mt-4 sm:grid-cols-2",
+            div {
+                label {
+                    r#for: "site-master",
+                    class: "text-primary dark:text-gray-200",
+                    "Master passphrase"
+                }
+                input {
+                    r#type: "password",
+                    class: "block w-full px-4 py-2 mt-2 text-gray-700 bg-white border border-gray-300 rounded-md dark:bg-gray-800 dark:text-gray-300 dark:border-gray-600 focus:border-blue-500 dark:focus:border-blue-500 focus:outline-none focus:ring",
+                    id: "site-master",
+                    autocomplete: "off",
+                    value: "{master}",
+                    oninput: move |event| master.set(event.value()),
+                }
+            }
+            div {
+                label { r#for: "site-name", class: "text-primary dark:text-gray-200", "Site" }
+                InputText {
+                    source: site,
+                    placeholder: "eg. example.com",
+                    id: "site-name",
+                }
+            }
+            div {
+                label {
+                    r#for: "site-counter",
+                    class: "text-primary dark:text-gray-200",
+                    "Counter, to change the password of a site"
+                }
+                InputNumber {
+                    source: counter,
+                    min: 1,
+                    max: MAX_SITE_COUNTER,
+                    id: "site-counter",
+                }
+            }
+        }
+        div {
+            label {
+                r#for: "site-password",
+                class: "text-primary dark:text-gray-200",
+                "Site password"
+            }
+            ReadOnlyInputText {
+                source: derived,
+                placeholder: "password",
+                id: "site-password",
+            }
+        }
+        if let Some(error) = error() {
+            p { class: "text-sm text-error pt-2", role: "alert", "{error}" }
+        }
+        div { class: "grid grid-cols-2 gap-6 mt-4",
+            div {
+                button {
+                    r#type: "button",
+                    onclick: move |event| {
+                        event.prevent_default();
+                        let settings = settings();
+                        let result = dictionary_characters(&settings)
+                            .ok_or(DeriveError::NoFixedCharacters)
+                            .and_then(|characters| {
+                                let counter = counter() as u32;
+                                derive::derive(&master(), &site(), counter, &characters, settings.length)
+                            });
+                        match result {
+                            Ok(password) => derived.set(password),
+                            Err(err) => error.set(Some(err)),
+                        }
+                    },
+                    class: "px-6 py-2 w-full leading-5 text-white-text transition-colors duration-200 transform bg-tertiary rounded-md hover:bg-quaternary focus:outline-none focus:bg-gray-600",
+                    "Derive"
+                }
+            }
+            CopyButton { text: derived }
+        }
+    }
+}
+
+/// Shows how strong the generated password is, from its entropy and the
+/// patterns an attacker would try first.
+#[component]
+fn StrengthMeter(entropy_bits: f64, strength: Strength) -> Element {
+    let score = strength.score();
+    let label = strength.label();
+
+    rsx! {
+        div { class: "strength mt-4",
+            div {
+                class: "strength-meter",
+                role: "meter",
+                aria_label: "Password strength",
+                aria_valuemin: 0,
+                aria_valuemax: 4,
+                aria_valuenow: score,
+                aria_valuetext: label,
+                div {
+                    class: "strength-meter-fill strength-score-{score}",
+                    style: "width: {(score as u32 + 1) * 20}%",
+                }
+            }
+            p { class: "text-sm text-primary dark:text-gray-200 pt-2",
+                "{label} · {entropy_bits:.0} bits of entropy · cracked in {strength.crack_time()} at 10 billion guesses per second"
+            }
+        }
+    }
+}
+
 #[component]
 pub fn PasswordGenerator() -> Element {
     info!("Creating Password Generator");
 
-    const DEFAULT_DICTIONARY: Dictionary = Dictionary::Ascii;
-    const DEFAULT_SIZE: i64 = 32;
-    const MAX_SIZE: i64 = 64;
-
-    let signal_dictionary = use_signal(|| Into::<String>::into(DEFAULT_DICTIONARY));
-    let signal_custom_dictionary = use_signal(|| "".to_string());
-    let signal_number_of_characters = use_signal(|| DEFAULT_SIZE);
+    let mut signal_mode = use_signal(|| Into::<String>::into(DEFAULT_MODE));
+    let mut signal_dictionary = use_signal(|| Into::<String>::into(DEFAULT_DICTIONARY));
+    let mut signal_custom_dictionary = use_signal(|| "".to_string());
+    let mut signal_normalize_unicode = use_signal(|| true);
+    let mut signal_number_of_characters = use_signal(|| DEFAULT_SIZE);
+    let mut signal_number_of_words = use_signal(|| DEFAULT_WORDS);
+    let mut signal_separator = use_signal(|| DEFAULT_SEPARATOR.to_string());
+    let mut signal_capitalization = use_signal(|| Into::<String>::into(DEFAULT_CAPITALIZATION));
+    let mut signal_insert_number = use_signal(|| false);
+    let mut signal_insert_symbol = use_signal(|| false);
+    let mut signal_min_lowercase = use_signal(|| 0);
+    let mut signal_min_uppercase = use_signal(|| 0);
+    let mut signal_min_digits = use_signal(|| 0);
+    let mut signal_min_symbols = use_signal(|| 0);
+    let mut signal_excluded = use_signal(|| "".to_string());
+    let mut signal_no_repeats = use_signal(|| false);
+    let mut signal_no_sequences = use_signal(|| false);
+    let mut signal_first = use_signal(|| Into::<String>::into(DEFAULT_FIRST));
     let mut signal_reset_password = use_signal(|| false);
 
+    let mode = Mode::from(signal_mode.read().as_str());
     let dictionary = Dictionary::from(signal_dictionary.read().as_str());
-    info!("Dictionary: {:?}", dictionary);
+    info!("Mode: {:?}, dictionary: {:?}", mode, dictionary);
 
-    let password = use_resource(move || async move {
-        signal_reset_password();
+    let passphrase_options = move || PassphraseOptions {
+        words: signal_number_of_words() as usize,
+        separator: signal_separator(),
+        capitalization: Capitalization::from(signal_capitalization().as_str()),
+        number: signal_insert_number(),
+        symbol: signal_insert_symbol(),
+    };
+
+    let policy = move || Policy {
+        min_lowercase: signal_min_lowercase() as usize,
+        min_uppercase: signal_min_uppercase() as usize,
+        min_digits: signal_min_digits() as usize,
+        min_symbols: signal_min_symbols() as usize,
+        excluded: signal_excluded(),
+        no_repeats: signal_no_repeats(),
+        no_sequences: signal_no_sequences(),
+        first: FirstCharacter::from(signal_first().as_str()),
+    };
+
+    // Only set from the browser, when loading the saved presets
+    #[allow(unused_mut)]
+    let mut signal_presets = use_signal(Vec::<Preset>::new);
+    #[allow(unused_mut)]
+    let mut signal_preset = use_signal(|| NO_PRESET.to_string());
+    #[allow(unused_mut)]
+    let mut signal_preset_message = use_signal(|| None::<Result<String, String>>);
+    // The preset the link asked for, when it isn't saved here
+    #[allow(unused_mut)]
+    let mut signal_missing_preset = use_signal(|| None::<String>);
 
-        get_password(
-            Dictionary::from(signal_dictionary().as_str()),
-            signal_custom_dictionary().as_str(),
-            signal_number_of_characters(),
-        )
+    let current_preset = use_memo(move || Preset {
+        name: String::new(),
+        mode: Mode::from(signal_mode().as_str()),
+        dictionary: Dictionary::from(signal_dictionary().as_str()),
+        custom_dictionary: signal_custom_dictionary(),
+        normalize_unicode: signal_normalize_unicode(),
+        length: signal_number_of_characters() as usize,
+        policy: policy(),
+        passphrase: passphrase_options(),
     });
 
-    let selectDictionaryPassword = rsx! {
-        Select {
-            source: signal_dictionary,
-            default: Into::<String>::into(DEFAULT_DICTIONARY),
-            values: enum_iterator::all::<Dictionary>().map(|x| x.into()).collect(),
-            id: "select-dictionary",
-        }
+    let mut apply_preset = move |preset: Preset| {
+        signal_mode.set(preset.mode.into());
+        signal_dictionary.set(preset.dictionary.into());
+        signal_custom_dictionary.set(preset.custom_dictionary);
+        signal_normalize_unicode.set(preset.normalize_unicode);
+        signal_number_of_characters.set(preset.length as i64);
+        signal_min_lowercase.set(preset.policy.min_lowercase as i64);
+        signal_min_uppercase.set(preset.policy.min_uppercase as i64);
+        signal_min_digits.set(preset.policy.min_digits as i64);
+        signal_min_symbols.set(preset.policy.min_symbols as i64);
+        signal_excluded.set(preset.policy.excluded);
+        signal_no_repeats.set(preset.policy.no_repeats);
+        signal_no_sequences.set(preset.policy.no_sequences);
+        signal_first.set(preset.policy.first.into());
+        signal_number_of_words.set(preset.passphrase.words as i64);
+        signal_separator.set(preset.passphrase.separator);
+        signal_capitalization.set(preset.passphrase.capitalization.into());
+        signal_insert_number.set(preset.passphrase.number);
+        signal_insert_symbol.set(preset.passphrase.symbol);
     };
+
+    // Read before the effect below takes the preset off the address bar
+    #[cfg(feature = "web")]
+    let shared_preset = use_hook(presets::preset_from_url);
+    // Saved presets are only known after hydration
+    #[cfg(feature = "web")]
+    use_effect(move || {
+        let saved = presets::load();
+        let shared = shared_preset.clone();
+        signal_presets.set(saved.clone());
+        if let Some(name) = shared {
+            if saved.iter().any(|preset| preset.name == name) {
+                signal_preset.set(name);
+            } else {
+                signal_missing_preset.set(Some(name));
+            }
+        }
+    });
+
+    use_effect(move || {
+        let name = signal_preset();
+        #[cfg(feature = "web")]
+        presets::update_url(Some(name.as_str()).filter(|name| *name != NO_PRESET));
+        let preset = signal_presets
+            .peek()
+            .iter()
+            .find(|preset| preset.name == name)
+            .cloned();
+        if let Some(preset) = preset {
+            apply_preset(preset);
+        }
+    });
+
+    let generated = use_resource(move || async move {
+        signal_reset_password();
+        generate(&current_preset())
+    });
+    let password = use_memo(move || generated().and_then(Result::ok).unwrap_or_default());
+    let policy_error = use_memo(move || generated().and_then(Result::err));
+
+    // What passwords are really picked from, to check the dictionary
+    let alphabet = use_memo(move || {
+        let preset = current_preset();
+        let characters = dictionary_characters(&preset)?;
+        Some(preset.policy.alphabet(&characters))
+    });
+
+    let strength = use_memo(move || {
+        // Nothing to rate before the first password, such as when pre-rendering
+        let password = Some(password()).filter(|password| !password.is_empty())?;
+        let entropy_bits = match Mode::from(signal_mode().as_str()) {
+            Mode::Characters => password_entropy(&current_preset(), &password),
+            Mode::Passphrase => passphrase_options().entropy(),
+        };
+        Some((entropy_bits, Strength::new(&password, entropy_bits)))
+    });
+
     rsx! {
         div { class: "relative bg-cover bg-center bg-no-repeat py-8 md:py-4 lg:py-8",
             div { class: "absolute inset-0 z-20 bg-gradient-to-r from-hero-gradient-from to-hero-gradient-to bg-cover bg-center bg-no-repeat" }
@@ -162,6 +940,13 @@ pub fn PasswordGenerator() -> Element {
         }
         section { class: "max-w-4xl p-6 mx-auto bg-indigo-600 rounded-md shadow-none lg:shadow-md lg:mt-8",
             form {
+                if let Some(name) = signal_missing_preset() {
+                    if !signal_presets.read().iter().any(|preset| preset.name == name) {
+                        p { class: "text-sm text-error mb-4", role: "alert",
+                            "The link is for a preset named \"{name}\", which isn't saved in this browser. Import it below to use it."
+                        }
+                    }
+                }
                 label {
                     r#for: "password-result",
                     class: "text-primary dark:text-gray-200",
@@ -169,32 +954,63 @@ pub fn PasswordGenerator() -> Element {
                 }
                 div {
                     ReadOnlyInputText {
-                        source: "{password().unwrap_or_default()}",
+                        source: "{password}",
                         placeholder: "password",
                         id: "password-result",
                     }
                 }
-                if dictionary != Dictionary::Custom {
-                    div { class: "grid grid-cols-1 gap-6 mt-4 sm:grid-cols-1",
+                if let Some(error) = policy_error() {
+                    p { class: "text-sm text-error pt-2", role: "alert", "{error}" }
+                }
+                if let Some((entropy_bits, strength)) = strength() {
+                    StrengthMeter { entropy_bits, strength }
+                }
+                div { class: "grid grid-cols-1 gap-6 mt-4 sm:grid-cols-2",
+                    div {
+                        label {
+                            r#for: "select-mode",
+                            class: "text-primary dark:text-gray-200",
+                            "Generate"
+                        }
+                        Select {
+                            source: signal_mode,
+                            default: Into::<String>::into(DEFAULT_MODE),
+                            values: enum_iterator::all::<Mode>().map(|x| x.into()).collect(),
+                            id: "select-mode",
+                        }
+                    }
+                    if mode == Mode::Characters {
                         div {
                             label {
                                 r#for: "select-dictionary",
                                 class: "text-primary dark:text-gray-200",
                                 "Select character set"
                             }
-                            {selectDictionaryPassword}
+                            Select {
+                                source: signal_dictionary,
+                                default: Into::<String>::into(DEFAULT_DICTIONARY),
+                                values: enum_iterator::all::<Dictionary>().map(|x| x.into()).collect(),
+                                id: "select-dictionary",
+                            }
                         }
-                    }
-                } else {
-                    div { class: "grid grid-cols-1 gap-6 mt-4 sm:grid-cols-2",
+                    } else {
                         div {
                             label {
-                                r#for: "select-dictionary",
+                                r#for: "select-capitalization",
                                 class: "text-primary dark:text-gray-200",
-                                "Select character set"
+                                "Capitalization"
+                            }
+                            Select {
+                                source: signal_capitalization,
+                                default: Into::<String>::into(DEFAULT_CAPITALIZATION),
+                                values: enum_iterator::all::<Capitalization>().map(|x| x.into()).collect(),
+                                id: "select-capitalization",
                             }
-                            {selectDictionaryPassword}
                         }
+                    }
+                }
+                if mode == Mode::Characters && dictionary == Dictionary::Custom {
+                    div { class: "grid grid-cols-1 gap-6 mt-4",
                         div {
                             label {
                                 r#for: "custom-dictionary",
@@ -206,23 +1022,94 @@ pub fn PasswordGenerator() -> Element {
                                 placeholder: "eg. abc123456",
                                 id: "custom-dictionary",
                             }
+                            Checkbox {
+                                source: signal_normalize_unicode,
+                                label: "Join accents typed apart into one character (NFC)",
+                                id: "normalize-unicode",
+                            }
+                        }
+                    }
+                }
+                if mode == Mode::Characters {
+                    if let Some(alphabet) = alphabet() {
+                        p { class: "text-sm text-primary dark:text-gray-200 pt-2",
+                            "Picking from {alphabet.len()} characters: "
+                            span { class: "alphabet", {alphabet.iter().collect::<String>()} }
+                        }
+                    }
+                }
+                if mode == Mode::Characters && dictionary != Dictionary::Unicode {
+                    PolicyOptions {
+                        min_lowercase: signal_min_lowercase,
+                        min_uppercase: signal_min_uppercase,
+                        min_digits: signal_min_digits,
+                        min_symbols: signal_min_symbols,
+                        max_count: MAX_CLASS_COUNT,
+                        excluded: signal_excluded,
+                        no_repeats: signal_no_repeats,
+                        no_sequences: signal_no_sequences,
+                        first: signal_first,
+                        default_first: Into::<String>::into(DEFAULT_FIRST),
+                    }
+                }
+                if mode == Mode::Passphrase {
+                    div { class: "grid grid-cols-1 gap-6 mt-4 sm:grid-cols-2",
+                        div {
+                            label {
+                                r#for: "passphrase-separator",
+                                class: "text-primary dark:text-gray-200",
+                                "Separator"
+                            }
+                            InputText {
+                                source: signal_separator,
+                                placeholder: "eg. -",
+                                id: "passphrase-separator",
+                            }
+                        }
+                        div {
+                            Checkbox {
+                                source: signal_insert_number,
+                                label: "Add a number",
+                                id: "passphrase-number",
+                            }
+                            Checkbox {
+                                source: signal_insert_symbol,
+                                label: "Add a symbol",
+                                id: "passphrase-symbol",
+                            }
                         }
                     }
                 }
                 div { class: "grid grid-cols-1 gap-6 mt-4",
-                    div {
-                        label {
-                            r#for: "range-password",
-                            class: "text-primary dark:text-gray-200",
-                            "Number of characters"
+                    if mode == Mode::Characters {
+                        div {
+                            label {
+                                r#for: "range-password",
+                                class: "text-primary dark:text-gray-200",
+                                "Number of characters"
+                            }
+                            Range {
+                                source: signal_number_of_characters,
+                                min: 1,
+                                max: MAX_SIZE,
+                                step: 1,
+                                id: "range-password",
+                            }
                         }
-                        Range {
-                            source: signal_number_of_characters,
-                            min: 1,
-                            max: MAX_SIZE,
-                            initial_value: DEFAULT_SIZE,
-                            step: 1,
-                            id: "range-password",
+                    } else {
+                        div {
+                            label {
+                                r#for: "range-words",
+                                class: "text-primary dark:text-gray-200",
+                                "Number of words"
+                            }
+                            Range {
+                                source: signal_number_of_words,
+                                min: 1,
+                                max: MAX_WORDS,
+                                step: 1,
+                                id: "range-words",
+                            }
                         }
                     }
                 }
@@ -240,7 +1127,129 @@ pub fn PasswordGenerator() -> Element {
                     }
                     CopyButton { text: password }
                 }
+                PresetOptions {
+                    saved: signal_presets,
+                    selected: signal_preset,
+                    current: current_preset,
+                    message: signal_preset_message,
+                }
+            }
+        }
+        section { class: "max-w-4xl p-6 mx-auto mt-8 bg-indigo-600 rounded-md shadow-none lg:shadow-md",
+            h2 { class: "font-header text-xl text-primary dark:text-gray-200",
+                "Bulk passwords"
+            }
+            p { class: "text-sm text-primary dark:text-gray-200 pt-2",
+                "Many passwords with the settings above, made in your browser like the rest."
+            }
+            form { BulkPasswords { settings: current_preset } }
+        }
+        section { class: "max-w-4xl p-6 mx-auto mt-8 bg-indigo-600 rounded-md shadow-none lg:shadow-md",
+            h2 { class: "font-header text-xl text-primary dark:text-gray-200",
+                "Site passwords"
+            }
+            p { class: "text-sm text-primary dark:text-gray-200 pt-2",
+                "The same master passphrase, site and counter always give the same password, from the character set and length above. Requirements don't apply to them."
+            }
+            form { SitePassword { settings: current_preset } }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    /// How many times each character is expected to be picked.
+    const DRAWS_PER_CHARACTER: usize = 500;
+    const CUSTOM_DICTIONARY: &str = "aab c\tc\u{0}de\u{301}é€";
+
+    /// The 99.9th percentile of the chi-square distribution with `degrees`
+    /// degrees of freedom, by the Wilson–Hilferty approximation.
+    fn chi_square_critical(degrees: usize) -> f64 {
+        // The 99.9th percentile of the standard normal distribution
+        const Z: f64 = 3.090;
+        let k = degrees as f64;
+        k * (1.0 - 2.0 / (9.0 * k) + Z * (2.0 / (9.0 * k)).sqrt()).powi(3)
+    }
+
+    fn assert_uniform(preset: &Preset) {
+        let characters = dictionary_characters(preset).unwrap();
+        let alphabet = preset.policy.alphabet(&characters);
+        let length = MAX_SIZE as usize;
+        let passwords = alphabet.len() * DRAWS_PER_CHARACTER / length + 1;
+        let mut rng = StdRng::seed_from_u64(25);
+        let mut counts = HashMap::<char, usize>::new();
+        for _ in 0..passwords {
+            let password = preset
+                .policy
+                .generate(&characters, length, &mut rng)
+                .unwrap();
+            for c in password.chars() {
+                *counts.entry(c).or_default() += 1;
             }
         }
+
+        let dictionary = preset.dictionary;
+        assert_eq!(counts.len(), alphabet.len(), "{dictionary:?}: {counts:?}");
+        let expected = (passwords * length) as f64 / alphabet.len() as f64;
+        let chi_square = counts
+            .values()
+            .map(|count| (*count as f64 - expected).powi(2) / expected)
+            .sum::<f64>();
+        let critical = chi_square_critical(alphabet.len() - 1);
+        assert!(
+            chi_square < critical,
+            "{dictionary:?}: {chi_square} over {critical}, {counts:?}"
+        );
+    }
+
+    #[test]
+    fn every_dictionary_samples_uniformly() {
+        let dictionaries = enum_iterator::all::<Dictionary>()
+            .filter(|dictionary| *dictionary != Dictionary::Unicode);
+        for dictionary in dictionaries {
+            assert_uniform(&Preset {
+                dictionary,
+                custom_dictionary: CUSTOM_DICTIONARY.to_string(),
+                ..Default::default()
+            });
+        }
+    }
+
+    #[test]
+    fn spanish_dictionary_repeats_no_character() {
+        // It has `?` twice, as on the keyboard
+        let preset = Preset {
+            dictionary: Dictionary::SpanishKeyboard,
+            ..Default::default()
+        };
+        let alphabet = preset.policy.alphabet(SPANISH_DICTIONARY);
+        assert_eq!(
+            alphabet.len(),
+            alphabet
+                .iter()
+                .collect::<std::collections::HashSet<_>>()
+                .len()
+        );
+        assert_eq!(alphabet.len(), SPANISH_DICTIONARY.chars().count() - 1);
+    }
+
+    #[test]
+    fn custom_dictionaries_are_normalized() {
+        let mut preset = Preset {
+            dictionary: Dictionary::Custom,
+            custom_dictionary: CUSTOM_DICTIONARY.to_string(),
+            ..Default::default()
+        };
+        assert_eq!(dictionary_characters(&preset).unwrap(), "abcdé€");
+
+        preset.normalize_unicode = false;
+        assert_eq!(dictionary_characters(&preset).unwrap(), "abcde\u{301}é€");
     }
 }
@@ -0,0 +1,54 @@
+//! Turns what people type as a custom dictionary into the characters passwords
+//! are picked from, each of them once, so none is likelier than the others.
+
+use unicode_normalization::UnicodeNormalization;
+
+/// Drops whitespace and control characters, which are hard to tell apart or
+/// type, and repeated characters. With `nfc`, accented letters typed as a
+/// letter and a combining mark become one character first.
+pub fn normalize(dictionary: &str, nfc: bool) -> String {
+    let composed = if nfc {
+        dictionary.nfc().collect()
+    } else {
+        dictionary.to_string()
+    };
+    unique(
+        composed
+            .chars()
+            .filter(|c| !c.is_whitespace() && !c.is_control()),
+    )
+}
+
+/// `characters` without repetitions, in the order they first appear.
+pub fn unique(characters: impl IntoIterator<Item = char>) -> String {
+    let mut alphabet = String::new();
+    for c in characters {
+        if !alphabet.contains(c) {
+            alphabet.push(c);
+        }
+    }
+    alphabet
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_characters_are_dropped() {
+        assert_eq!(normalize("aab", false), "ab");
+        assert_eq!(normalize("abcabc", true), "abc");
+    }
+
+    #[test]
+    fn whitespace_and_control_characters_are_dropped() {
+        assert_eq!(normalize(" a\tb\nc\u{0}d\u{7f}e\u{a0}", false), "abcde");
+    }
+
+    #[test]
+    fn nfc_composes_accents() {
+        let decomposed = "e\u{301}a\u{301}é";
+        assert_eq!(normalize(decomposed, true), "éá");
+        assert_eq!(normalize(decomposed, false), "e\u{301}aé");
+    }
+}
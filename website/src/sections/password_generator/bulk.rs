@@ -0,0 +1,123 @@
+//! Files of many passwords, made in the browser.
+
+/// The formats bulk passwords download as.
+#[derive(Debug, Clone, Copy, PartialEq, enum_iterator::Sequence)]
+pub enum Format {
+    /// A `password` column, as RFC 4180 describes. Passwords spreadsheets
+    /// could take for formulas are quoted, see [`csv_field`].
+    Csv,
+    /// An array of strings.
+    Json,
+    /// One password per line. Passwords with line breaks, which the Unicode
+    /// dictionary can make, are ambiguous in it.
+    Text,
+}
+
+impl From<&str> for Format {
+    fn from(value: &str) -> Self {
+        match value {
+            "CSV" => Format::Csv,
+            "JSON" => Format::Json,
+            "Text" => Format::Text,
+            _ => Format::Csv,
+        }
+    }
+}
+
+impl From<Format> for String {
+    fn from(value: Format) -> Self {
+        match value {
+            Format::Csv => "CSV".to_string(),
+            Format::Json => "JSON".to_string(),
+            Format::Text => "Text".to_string(),
+        }
+    }
+}
+
+/// The first characters that make spreadsheets read a field as a formula.
+const FORMULA_STARTS: [char; 6] = ['=', '+', '-', '@', '\t', '\r'];
+
+/// Quotes `field` if it has anything CSV would split it on, or if it starts
+/// like a formula. Quoted fields are kept as text by importers that honor the
+/// quotes, but Excel evaluates them anyway, which the download warns about.
+/// Prefixing them instead, as is usual against CSV injection, would change
+/// the password.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) || field.starts_with(FORMULA_STARTS) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+impl Format {
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Format::Csv => "passwords.csv",
+            Format::Json => "passwords.json",
+            Format::Text => "passwords.txt",
+        }
+    }
+
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            Format::Csv => "text/csv",
+            Format::Json => "application/json",
+            Format::Text => "text/plain",
+        }
+    }
+
+    pub fn render(self, passwords: &[String]) -> String {
+        match self {
+            Format::Csv => std::iter::once("password".to_string())
+                .chain(passwords.iter().map(|password| csv_field(password)))
+                .map(|line| line + "\r\n")
+                .collect(),
+            Format::Json => {
+                serde_json::to_string_pretty(passwords).expect("strings always serialize")
+            }
+            Format::Text => passwords
+                .iter()
+                .map(|password| password.clone() + "\n")
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn passwords(passwords: &[&str]) -> Vec<String> {
+        passwords
+            .iter()
+            .map(|password| password.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn csv_quotes_what_it_would_split_on() {
+        assert_eq!(
+            Format::Csv.render(&passwords(&["plain", "a,b", "say \"hi\"", "two\nlines"])),
+            "password\r\nplain\r\n\"a,b\"\r\n\"say \"\"hi\"\"\"\r\n\"two\nlines\"\r\n"
+        );
+    }
+
+    #[test]
+    fn csv_quotes_formulas() {
+        assert_eq!(
+            Format::Csv.render(&passwords(&["=1+1", "+a", "-a", "@a", "a=b"])),
+            "password\r\n\"=1+1\"\r\n\"+a\"\r\n\"-a\"\r\n\"@a\"\r\na=b\r\n"
+        );
+    }
+
+    #[test]
+    fn json_and_text() {
+        let passwords = passwords(&["a\"b", "c"]);
+        assert_eq!(
+            serde_json::from_str::<Vec<String>>(&Format::Json.render(&passwords)).unwrap(),
+            passwords
+        );
+        assert_eq!(Format::Text.render(&passwords), "a\"b\nc\n");
+    }
+}
@@ -0,0 +1,274 @@
+//! Site passwords derived from a master passphrase, in the style of LessPass
+//! or Spectre: the same passphrase, site and counter always give the same
+//! password, so nothing needs to be stored.
+//!
+//! The passphrase is NFC-normalized, so it's the same however its accents were
+//! typed, then goes through Argon2id, salted with the site and counter, and
+//! its output seeds a ChaCha20 stream. Characters are picked from the stream by
+//! rejection sampling, so each one is equally likely. Changing any step
+//! changes every derived password, which the test vectors below catch.
+
+use std::fmt;
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use rand_chacha::rand_core::{RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use unicode_normalization::UnicodeNormalization;
+
+use super::alphabet::unique;
+
+/// Memory cost in KiB, the 19 MiB OWASP recommends for Argon2id.
+const MEMORY_COST: u32 = 19 * 1024;
+const TIME_COST: u32 = 2;
+const PARALLELISM: u32 = 1;
+/// Starts every salt, so the passphrase derives nothing else the same way.
+const SALT_PREFIX: &[u8] = b"password-generator/site-password/v1";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeriveError {
+    MissingMaster,
+    MissingSite,
+    /// The Unicode dictionary has no fixed characters to map onto.
+    NoFixedCharacters,
+    NoCharacters,
+    Argon2(argon2::Error),
+}
+
+impl fmt::Display for DeriveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DeriveError::MissingMaster => write!(f, "Enter your master passphrase"),
+            DeriveError::MissingSite => write!(f, "Enter the site the password is for"),
+            DeriveError::NoFixedCharacters => write!(
+                f,
+                "Site passwords need a character set with fixed characters, unlike Unicode"
+            ),
+            DeriveError::NoCharacters => write!(f, "There are no characters to pick from"),
+            DeriveError::Argon2(err) => write!(f, "Failed to derive the password: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for DeriveError {}
+
+/// Sites are matched ignoring case and surrounding spaces, as people type
+/// them differently.
+fn normalize_site(site: &str) -> String {
+    site.trim().to_lowercase()
+}
+
+/// The prefix, then the site and counter, with the length of the site so no
+/// two of them make the same salt.
+fn salt(site: &str, counter: u32) -> Vec<u8> {
+    let mut salt = SALT_PREFIX.to_vec();
+    salt.extend_from_slice(&(site.len() as u32).to_be_bytes());
+    salt.extend_from_slice(site.as_bytes());
+    salt.extend_from_slice(&counter.to_be_bytes());
+    salt
+}
+
+/// A uniform index below `len`. Values from the top of the range, where they
+/// would favor the smaller indices, are drawn again.
+fn uniform_index(rng: &mut impl RngCore, len: usize) -> usize {
+    let len = len as u64;
+    let range = 1u64 << 32;
+    let zone = range - range % len;
+    loop {
+        let value = u64::from(rng.next_u32());
+        if value < zone {
+            return (value % len) as usize;
+        }
+    }
+}
+
+/// The password for `site` with `counter`, of `length` characters from
+/// `characters`. Repeated characters count once, so they aren't likelier.
+pub fn derive(
+    master: &str,
+    site: &str,
+    counter: u32,
+    characters: &str,
+    length: usize,
+) -> Result<String, DeriveError> {
+    if master.is_empty() {
+        return Err(DeriveError::MissingMaster);
+    }
+    let master = master.nfc().collect::<String>();
+    let site = normalize_site(site);
+    if site.is_empty() {
+        return Err(DeriveError::MissingSite);
+    }
+    let alphabet = unique(characters.chars()).chars().collect::<Vec<_>>();
+    if alphabet.is_empty() {
+        return Err(DeriveError::NoCharacters);
+    }
+
+    let params =
+        Params::new(MEMORY_COST, TIME_COST, PARALLELISM, Some(32)).map_err(DeriveError::Argon2)?;
+    let mut seed = [0; 32];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(master.as_bytes(), &salt(&site, counter), &mut seed)
+        .map_err(DeriveError::Argon2)?;
+
+    let mut rng = ChaCha20Rng::from_seed(seed);
+    Ok((0..length)
+        .map(|_| alphabet[uniform_index(&mut rng, alphabet.len())])
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use super::super::presets::Preset;
+    use super::super::{dictionary_characters, Dictionary};
+
+    const MASTER: &str = "correct horse battery staple";
+    /// Spanish letters with their accents typed apart, as combining marks.
+    const DECOMPOSED: &str = "abcn\u{303}a\u{301}e\u{301}i\u{301}o\u{301}u\u{301}?¿";
+
+    /// The characters the generator derives from with `dictionary`.
+    fn characters(dictionary: Dictionary, custom_dictionary: &str) -> String {
+        let preset = Preset {
+            dictionary,
+            custom_dictionary: custom_dictionary.to_string(),
+            ..Default::default()
+        };
+        dictionary_characters(&preset).unwrap().into_owned()
+    }
+
+    /// Derived passwords must never change, or people lose their passwords.
+    /// They go through the dictionaries the generator uses, so changing one
+    /// is caught too.
+    #[test]
+    #[rustfmt::skip]
+    fn test_vectors() {
+        let vectors = [
+            (MASTER, "example.com", 1, Dictionary::Readable, "", 16, "jmp8fQhD2xr2aeiT"),
+            (MASTER, "example.com", 1, Dictionary::Alphanumeric, "", 16, "hqDMXn29EFlNz9bu"),
+            (MASTER, "example.com", 2, Dictionary::Alphanumeric, "", 16, "HlTOYPrUwKLG8N6k"),
+            (MASTER, "example.org", 1, Dictionary::Alphanumeric, "", 16, "1RrflNBwfujUALCy"),
+            (MASTER, "example.com", 1, Dictionary::Ascii, "", 16, "l+tmBVC,k\\(jD@4_"),
+            (MASTER, "example.com", 1, Dictionary::SpanishKeyboard, "", 16, "L=m)íÌúòóu%Ñd%á>"),
+            ("hunter2", "example.com", 1, Dictionary::Custom, "0123456789", 6, "734196"),
+            ("contraseña", "correo.es", 1, Dictionary::Custom, DECOMPOSED, 12, "¿ábíúéúúé?íí"),
+        ];
+        for (master, site, counter, dictionary, custom, length, expected) in vectors {
+            let characters = characters(dictionary, custom);
+            let derived = derive(master, site, counter, &characters, length).unwrap();
+            assert_eq!(
+                derived, expected,
+                "{master}, {site}, {counter}, {dictionary:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn the_unicode_dictionary_has_no_fixed_characters() {
+        let preset = Preset {
+            dictionary: Dictionary::Unicode,
+            ..Default::default()
+        };
+        assert!(dictionary_characters(&preset).is_none());
+    }
+
+    #[test]
+    fn custom_dictionaries_are_normalized() {
+        assert_eq!(characters(Dictionary::Custom, DECOMPOSED), "abcñáéíóú?¿");
+    }
+
+    #[test]
+    fn passphrases_are_normalized() {
+        let digits = characters(Dictionary::Custom, "0123456789");
+        assert_eq!(
+            derive("contrasen\u{303}a", "correo.es", 1, &digits, 8).unwrap(),
+            derive("contraseña", "correo.es", 1, &digits, 8).unwrap()
+        );
+    }
+
+    #[test]
+    fn sites_ignore_case_and_spaces() {
+        assert_eq!(
+            derive("hunter2", " Example.COM ", 1, "0123456789", 6).unwrap(),
+            "734196"
+        );
+    }
+
+    #[test]
+    fn repeated_characters_count_once() {
+        assert_eq!(
+            derive("hunter2", "example.com", 1, "00123456789", 6).unwrap(),
+            "734196"
+        );
+    }
+
+    #[test]
+    fn missing_inputs() {
+        let alphanumeric = characters(Dictionary::Alphanumeric, "");
+        assert!(matches!(
+            derive("", "example.com", 1, &alphanumeric, 16),
+            Err(DeriveError::MissingMaster)
+        ));
+        assert!(matches!(
+            derive("hunter2", "  ", 1, &alphanumeric, 16),
+            Err(DeriveError::MissingSite)
+        ));
+        assert!(matches!(
+            derive("hunter2", "example.com", 1, "", 16),
+            Err(DeriveError::NoCharacters)
+        ));
+    }
+
+    /// Indices below a length that doesn't divide 2^32 should still be
+    /// uniform, which a plain modulo wouldn't make them for large lengths.
+    #[test]
+    fn uniform_indices() {
+        const LEN: usize = 7;
+        const SAMPLES: usize = 70_000;
+        let mut rng = ChaCha20Rng::seed_from_u64(7);
+        let mut counts = [0usize; LEN];
+        for _ in 0..SAMPLES {
+            counts[uniform_index(&mut rng, LEN)] += 1;
+        }
+
+        let expected = (SAMPLES / LEN) as f64;
+        let chi_square: f64 = counts
+            .iter()
+            .map(|&count| (count as f64 - expected).powi(2) / expected)
+            .sum();
+        // The 0.001 critical value with 6 degrees of freedom
+        assert!(chi_square < 22.46, "chi-square {chi_square}, {counts:?}");
+    }
+
+    #[test]
+    fn rejects_the_top_of_the_range() {
+        /// Yields the values given, then zeros.
+        struct Values(Vec<u32>);
+        impl RngCore for Values {
+            fn next_u32(&mut self) -> u32 {
+                if self.0.is_empty() {
+                    0
+                } else {
+                    self.0.remove(0)
+                }
+            }
+            fn next_u64(&mut self) -> u64 {
+                u64::from(self.next_u32())
+            }
+            fn fill_bytes(&mut self, dest: &mut [u8]) {
+                dest.fill(0);
+            }
+            fn try_fill_bytes(
+                &mut self,
+                dest: &mut [u8],
+            ) -> Result<(), rand_chacha::rand_core::Error> {
+                self.fill_bytes(dest);
+                Ok(())
+            }
+        }
+
+        // 2^32 = 3 * 1431655765 + 1, so only u32::MAX is out of the zone
+        let mut rng = Values(vec![u32::MAX, u32::MAX - 1]);
+        assert_eq!(uniform_index(&mut rng, 3), ((u32::MAX - 1) % 3) as usize);
+    }
+}
@@ -0,0 +1,254 @@
+//! Passphrases of random words from the EFF large wordlist, 7776 words that
+//! are easy to type and tell apart. The list is kept without the dice rolls
+//! it was published with, as the words are picked by the generator.
+//!
+//! The wordlist is by the Electronic Frontier Foundation, under CC BY 3.0 US:
+//! <https://www.eff.org/deeplinks/2016/07/new-wordlists-random-passphrases>
+
+use std::sync::LazyLock;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+static WORDS: LazyLock<Vec<&'static str>> =
+    LazyLock::new(|| include_str!("eff_large_wordlist.txt").lines().collect());
+
+/// The symbols that can be inserted, none of which is in the wordlist.
+const SYMBOLS: &[char] = &['!', '#', '$', '%', '&', '*', '+', '=', '?', '@', '^', '~'];
+
+// Serialized as they are shown
+#[derive(Debug, Clone, Copy, PartialEq, enum_iterator::Sequence, Serialize, Deserialize)]
+pub enum Capitalization {
+    #[serde(rename = "lowercase")]
+    Lowercase,
+    Capitalized,
+    #[serde(rename = "UPPERCASE")]
+    Uppercase,
+    /// Each word capitalized or not at random, for one more bit per word.
+    Random,
+}
+
+impl From<&str> for Capitalization {
+    fn from(value: &str) -> Self {
+        match value {
+            "lowercase" => Capitalization::Lowercase,
+            "Capitalized" => Capitalization::Capitalized,
+            "UPPERCASE" => Capitalization::Uppercase,
+            "Random" => Capitalization::Random,
+            _ => Capitalization::Lowercase,
+        }
+    }
+}
+
+impl From<Capitalization> for String {
+    fn from(value: Capitalization) -> Self {
+        match value {
+            Capitalization::Lowercase => "lowercase".to_string(),
+            Capitalization::Capitalized => "Capitalized".to_string(),
+            Capitalization::Uppercase => "UPPERCASE".to_string(),
+            Capitalization::Random => "Random".to_string(),
+        }
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+impl Capitalization {
+    fn apply(self, word: &str, rng: &mut impl Rng) -> String {
+        match self {
+            Capitalization::Lowercase => word.to_string(),
+            Capitalization::Capitalized => capitalize(word),
+            Capitalization::Uppercase => word.to_uppercase(),
+            Capitalization::Random if rng.gen() => capitalize(word),
+            Capitalization::Random => word.to_string(),
+        }
+    }
+}
+
+/// How to build a passphrase.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PassphraseOptions {
+    pub words: usize,
+    pub separator: String,
+    pub capitalization: Capitalization,
+    /// Appends a digit to one of the words.
+    pub number: bool,
+    /// Appends one of a few symbols to one of the words.
+    pub symbol: bool,
+}
+
+impl Default for PassphraseOptions {
+    fn default() -> Self {
+        Self {
+            words: super::DEFAULT_WORDS as usize,
+            separator: super::DEFAULT_SEPARATOR.to_string(),
+            capitalization: super::DEFAULT_CAPITALIZATION,
+            number: false,
+            symbol: false,
+        }
+    }
+}
+
+impl PassphraseOptions {
+    pub fn generate(&self, rng: &mut impl Rng) -> String {
+        let mut words = (0..self.words)
+            .filter_map(|_| {
+                let word = WORDS.choose(rng)?;
+                Some(self.capitalization.apply(word, rng))
+            })
+            .collect::<Vec<_>>();
+        if words.is_empty() {
+            return String::new();
+        }
+
+        if self.number {
+            let index = rng.gen_range(0..words.len());
+            words[index] += &rng.gen_range(0..10).to_string();
+        }
+        if self.symbol {
+            let index = rng.gen_range(0..words.len());
+            words[index].push(SYMBOLS[rng.gen_range(0..SYMBOLS.len())]);
+        }
+        words.join(&self.separator)
+    }
+
+    /// Bits of entropy of the passphrases these options make. The separator
+    /// adds none, as it's the same between every word.
+    pub fn entropy(&self) -> f64 {
+        if self.words == 0 {
+            return 0.0;
+        }
+        let words = self.words as f64;
+        let mut bits = words * (WORDS.len() as f64).log2();
+        if self.capitalization == Capitalization::Random {
+            bits += words;
+        }
+        // Which word gets it, and which one it is
+        if self.number {
+            bits += words.log2() + 10f64.log2();
+        }
+        if self.symbol {
+            bits += words.log2() + (SYMBOLS.len() as f64).log2();
+        }
+        bits
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    fn options(words: usize, separator: &str) -> PassphraseOptions {
+        PassphraseOptions {
+            words,
+            separator: separator.to_string(),
+            capitalization: Capitalization::Lowercase,
+            number: false,
+            symbol: false,
+        }
+    }
+
+    #[test]
+    fn wordlist_has_7776_unique_words() {
+        assert_eq!(WORDS.len(), 7776);
+        assert_eq!(WORDS.iter().collect::<HashSet<_>>().len(), 7776);
+        assert!(WORDS.iter().all(
+            |word| !word.is_empty() && word.chars().all(|c| c.is_ascii_lowercase() || c == '-')
+        ));
+    }
+
+    #[test]
+    fn passphrases_are_words_of_the_list() {
+        let mut rng = StdRng::seed_from_u64(20);
+        for (words, separator) in [(1, "-"), (6, "-"), (8, " "), (4, ".:")] {
+            let passphrase = options(words, separator).generate(&mut rng);
+            // Some words have a `-` of their own
+            let picked = match separator {
+                "-" => passphrase.matches('-').count() + 1,
+                _ => passphrase.split(separator).count(),
+            };
+            assert!(picked >= words, "{passphrase}");
+            if separator != "-" {
+                assert_eq!(picked, words, "{passphrase}");
+                assert!(
+                    passphrase
+                        .split(separator)
+                        .all(|word| WORDS.contains(&word)),
+                    "{passphrase}"
+                );
+            }
+        }
+        assert_eq!(options(0, "-").generate(&mut rng), "");
+    }
+
+    #[test]
+    fn capitalization_number_and_symbol() {
+        let mut rng = StdRng::seed_from_u64(20);
+        let options = PassphraseOptions {
+            capitalization: Capitalization::Uppercase,
+            number: true,
+            symbol: true,
+            ..options(5, " ")
+        };
+        let passphrase = options.generate(&mut rng);
+        assert_eq!(
+            passphrase.chars().filter(char::is_ascii_digit).count(),
+            1,
+            "{passphrase}"
+        );
+        assert_eq!(
+            passphrase.chars().filter(|c| SYMBOLS.contains(c)).count(),
+            1,
+            "{passphrase}"
+        );
+        let words = passphrase
+            .split(' ')
+            .map(|word| word.trim_end_matches(|c: char| c.is_ascii_digit() || SYMBOLS.contains(&c)))
+            .collect::<Vec<_>>();
+        assert_eq!(words.len(), 5);
+        for word in words {
+            assert_eq!(word, word.to_uppercase(), "{passphrase}");
+            assert!(
+                WORDS.contains(&word.to_lowercase().as_str()),
+                "{passphrase}"
+            );
+        }
+    }
+
+    #[test]
+    fn entropy() {
+        let word_bits = 7776f64.log2();
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-9;
+        assert!(close(options(6, "-").entropy(), 6.0 * word_bits));
+        assert!(close(options(6, " ").entropy(), 6.0 * word_bits));
+        assert_eq!(options(0, "-").entropy(), 0.0);
+
+        let random_case = PassphraseOptions {
+            capitalization: Capitalization::Random,
+            ..options(4, "-")
+        };
+        assert!(close(random_case.entropy(), 4.0 * word_bits + 4.0));
+        let extras = PassphraseOptions {
+            number: true,
+            symbol: true,
+            ..options(4, "-")
+        };
+        assert!(close(
+            extras.entropy(),
+            4.0 * word_bits + 2.0 * 4f64.log2() + 10f64.log2() + 12f64.log2()
+        ));
+    }
+}
@@ -0,0 +1,640 @@
+//! Rules generated passwords have to follow, such as those of sites asking for
+//! a digit and a symbol.
+//!
+//! Passwords are drawn uniformly from the allowed characters and thrown away
+//! until one follows the rules. Unlike placing the required characters first,
+//! this keeps every valid password equally likely. How many are drawn
+//! follows from the chance of a password following the rules, and rules too
+//! unlikely to be met are refused before drawing any.
+
+use std::fmt;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::alphabet::unique;
+
+/// The most passwords drawn for one that follows the rules. Rules needing
+/// more are refused without drawing any.
+const MAX_ATTEMPTS: usize = 100_000;
+/// The chance of giving up on rules that can be met.
+const GIVE_UP_CHANCE: f64 = 1e-9;
+/// How many more passwords are drawn than the estimated acceptance needs, as
+/// it takes the rules to be independent, which they aren't quite.
+const ESTIMATE_MARGIN: f64 = 4.0;
+/// How long repeated or sequential runs have to be to be rejected.
+const RUN_LENGTH: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, enum_iterator::Sequence)]
+pub enum CharClass {
+    Lowercase,
+    Uppercase,
+    Digit,
+    /// Anything else, such as punctuation.
+    Symbol,
+}
+
+impl CharClass {
+    pub fn of(c: char) -> Self {
+        if c.is_lowercase() {
+            CharClass::Lowercase
+        } else if c.is_uppercase() {
+            CharClass::Uppercase
+        } else if c.is_numeric() {
+            CharClass::Digit
+        } else {
+            CharClass::Symbol
+        }
+    }
+}
+
+impl fmt::Display for CharClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CharClass::Lowercase => write!(f, "lowercase letter"),
+            CharClass::Uppercase => write!(f, "uppercase letter"),
+            CharClass::Digit => write!(f, "digit"),
+            CharClass::Symbol => write!(f, "symbol"),
+        }
+    }
+}
+
+/// What the first character of a password has to be.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, enum_iterator::Sequence, Serialize, Deserialize,
+)]
+pub enum FirstCharacter {
+    #[default]
+    Any,
+    Letter,
+    Lowercase,
+    Uppercase,
+    Digit,
+    Symbol,
+}
+
+impl FirstCharacter {
+    pub fn allows(self, class: CharClass) -> bool {
+        match self {
+            FirstCharacter::Any => true,
+            FirstCharacter::Letter => {
+                matches!(class, CharClass::Lowercase | CharClass::Uppercase)
+            }
+            FirstCharacter::Lowercase => class == CharClass::Lowercase,
+            FirstCharacter::Uppercase => class == CharClass::Uppercase,
+            FirstCharacter::Digit => class == CharClass::Digit,
+            FirstCharacter::Symbol => class == CharClass::Symbol,
+        }
+    }
+}
+
+impl From<&str> for FirstCharacter {
+    fn from(value: &str) -> Self {
+        match value {
+            "Any" => FirstCharacter::Any,
+            "Letter" => FirstCharacter::Letter,
+            "Lowercase" => FirstCharacter::Lowercase,
+            "Uppercase" => FirstCharacter::Uppercase,
+            "Digit" => FirstCharacter::Digit,
+            "Symbol" => FirstCharacter::Symbol,
+            _ => FirstCharacter::Any,
+        }
+    }
+}
+
+impl From<FirstCharacter> for String {
+    fn from(value: FirstCharacter) -> Self {
+        match value {
+            FirstCharacter::Any => "Any".to_string(),
+            FirstCharacter::Letter => "Letter".to_string(),
+            FirstCharacter::Lowercase => "Lowercase".to_string(),
+            FirstCharacter::Uppercase => "Uppercase".to_string(),
+            FirstCharacter::Digit => "Digit".to_string(),
+            FirstCharacter::Symbol => "Symbol".to_string(),
+        }
+    }
+}
+
+/// Why no password can follow a [`Policy`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum PolicyError {
+    /// Every character of the dictionary is excluded, or it had none.
+    NoCharacters,
+    /// The rules need a class the dictionary has no characters of.
+    MissingClass(CharClass),
+    /// The minimum counts add up to more than the length.
+    TooShort { required: usize, length: usize },
+    /// Repeats are rejected, but there's a single character to repeat.
+    SingleCharacter,
+    /// Passwords following the rules are too rare to be drawn.
+    TooStrict,
+}
+
+impl fmt::Display for PolicyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PolicyError::NoCharacters => write!(f, "There are no characters to pick from"),
+            PolicyError::MissingClass(class) => {
+                write!(f, "The character set has no {class} to pick")
+            }
+            PolicyError::TooShort { required, length } => write!(
+                f,
+                "The requirements need {required} characters, but the password has {length}"
+            ),
+            PolicyError::SingleCharacter => write!(
+                f,
+                "The character set has a single character, so the password can only repeat it"
+            ),
+            PolicyError::TooStrict => write!(
+                f,
+                "The requirements are too unlikely to meet, try a longer password or fewer rules"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PolicyError {}
+
+/// Rules for generated passwords. The default one allows anything.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Policy {
+    pub min_lowercase: usize,
+    pub min_uppercase: usize,
+    pub min_digits: usize,
+    pub min_symbols: usize,
+    /// Characters never to use.
+    pub excluded: String,
+    /// Rejects a character repeated in a row, such as `aaa`.
+    pub no_repeats: bool,
+    /// Rejects consecutive characters, such as `abc` or `321`.
+    pub no_sequences: bool,
+    pub first: FirstCharacter,
+}
+
+impl Policy {
+    pub fn min_count(&self, class: CharClass) -> usize {
+        match class {
+            CharClass::Lowercase => self.min_lowercase,
+            CharClass::Uppercase => self.min_uppercase,
+            CharClass::Digit => self.min_digits,
+            CharClass::Symbol => self.min_symbols,
+        }
+    }
+
+    /// The characters of `dictionary` that aren't excluded, each of them once
+    /// so repeating one doesn't make it likelier.
+    pub fn alphabet(&self, dictionary: &str) -> Vec<char> {
+        unique(dictionary.chars().filter(|c| !self.excluded.contains(*c)))
+            .chars()
+            .collect()
+    }
+
+    /// Checks that passwords of `length` characters of `alphabet` can follow
+    /// the rules, rather than trying forever.
+    pub fn check(&self, alphabet: &[char], length: usize) -> Result<(), PolicyError> {
+        if alphabet.is_empty() {
+            return Err(PolicyError::NoCharacters);
+        }
+        let has = |class| alphabet.iter().any(|c| CharClass::of(*c) == class);
+        for class in enum_iterator::all::<CharClass>() {
+            if self.min_count(class) > 0 && !has(class) {
+                return Err(PolicyError::MissingClass(class));
+            }
+        }
+        if !enum_iterator::all::<CharClass>().any(|class| self.first.allows(class) && has(class)) {
+            return Err(
+                match enum_iterator::all::<CharClass>().find(|class| self.first.allows(*class)) {
+                    Some(class) => PolicyError::MissingClass(class),
+                    None => PolicyError::NoCharacters,
+                },
+            );
+        }
+        let required = enum_iterator::all::<CharClass>()
+            .map(|class| self.min_count(class))
+            .sum();
+        if required > length {
+            return Err(PolicyError::TooShort { required, length });
+        }
+        if self.no_repeats && alphabet.len() == 1 && length >= RUN_LENGTH {
+            return Err(PolicyError::SingleCharacter);
+        }
+        Ok(())
+    }
+
+    /// Roughly how likely a password of `length` characters drawn from
+    /// `alphabet` is to follow the rules. It's exact but for the runs, which
+    /// are taken as independent.
+    fn acceptance(&self, alphabet: &[char], length: usize) -> f64 {
+        if alphabet.is_empty() {
+            return 0.0;
+        }
+        let classes = enum_iterator::all::<CharClass>().collect::<Vec<_>>();
+        let shares = classes
+            .iter()
+            .map(|class| {
+                alphabet
+                    .iter()
+                    .filter(|c| CharClass::of(**c) == *class)
+                    .count() as f64
+                    / alphabet.len() as f64
+            })
+            .collect::<Vec<_>>();
+        let min_counts = classes
+            .iter()
+            .map(|class| self.min_count(*class))
+            .collect::<Vec<_>>();
+
+        let acceptance = match length {
+            0 => 1.0,
+            // The first character counts towards its class
+            _ => (0..classes.len())
+                .filter(|&first| self.first.allows(classes[first]))
+                .map(|first| {
+                    let mut min_counts = min_counts.clone();
+                    min_counts[first] = min_counts[first].saturating_sub(1);
+                    shares[first] * at_least_each(length - 1, &shares, &min_counts)
+                })
+                .sum(),
+        };
+        // Each run starting after the first characters makes the next one a
+        // repeat with one chance in the alphabet size, or a sequence with at
+        // most two
+        let run_chance = (self.no_repeats as u8 + 2 * self.no_sequences as u8) as f64
+            / (alphabet.len() as f64).powi(RUN_LENGTH as i32 - 1);
+        let runs = length.saturating_sub(RUN_LENGTH - 1);
+        acceptance * (1.0 - run_chance).max(0.0).powi(runs as i32)
+    }
+
+    /// Whether `password` follows the rules.
+    pub fn allows(&self, password: &[char]) -> bool {
+        if password.iter().any(|c| self.excluded.contains(*c)) {
+            return false;
+        }
+        for class in enum_iterator::all::<CharClass>() {
+            let count = password
+                .iter()
+                .filter(|c| CharClass::of(**c) == class)
+                .count();
+            if count < self.min_count(class) {
+                return false;
+            }
+        }
+        if let Some(first) = password.first() {
+            if !self.first.allows(CharClass::of(*first)) {
+                return false;
+            }
+        }
+        if !self.no_repeats && !self.no_sequences {
+            return true;
+        }
+        password.windows(RUN_LENGTH).all(|run| {
+            let step = run[1] as i64 - run[0] as i64;
+            let steady = run
+                .windows(2)
+                .all(|pair| pair[1] as i64 - pair[0] as i64 == step);
+            let rejected = (self.no_repeats && step == 0) || (self.no_sequences && step.abs() == 1);
+            !(steady && rejected)
+        })
+    }
+
+    /// A password of `length` characters from `dictionary` following the
+    /// rules, every such password being as likely.
+    pub fn generate(
+        &self,
+        dictionary: &str,
+        length: usize,
+        rng: &mut impl Rng,
+    ) -> Result<String, PolicyError> {
+        let alphabet = self.alphabet(dictionary);
+        self.check(&alphabet, length)?;
+        // Enough draws to find one with all but `GIVE_UP_CHANCE`, as
+        // `(1 - p)^n <= e^(-pn)`
+        let attempts = ESTIMATE_MARGIN * -GIVE_UP_CHANCE.ln() / self.acceptance(&alphabet, length);
+        if attempts > MAX_ATTEMPTS as f64 {
+            return Err(PolicyError::TooStrict);
+        }
+
+        let mut password = Vec::with_capacity(length);
+        for _ in 0..attempts.ceil() as usize {
+            password.clear();
+            password.extend((0..length).map(|_| alphabet[rng.gen_range(0..alphabet.len())]));
+            if self.allows(&password) {
+                return Ok(password.into_iter().collect());
+            }
+        }
+        Err(PolicyError::TooStrict)
+    }
+}
+
+/// The chance that `length` draws have at least `min_counts[i]` of each
+/// outcome `i`, drawn with chance `shares[i]`, from the multinomial
+/// distribution.
+fn at_least_each(length: usize, shares: &[f64], min_counts: &[usize]) -> f64 {
+    // terms[n]: for the outcomes so far adding up to `n` draws, the sum of
+    // `share^count / count!` over every allowed count of each
+    let mut terms = vec![0.0; length + 1];
+    terms[0] = 1.0;
+    for (share, &min_count) in shares.iter().zip(min_counts) {
+        let mut next = vec![0.0; length + 1];
+        for (used, before) in terms.iter().enumerate() {
+            let mut term = (1..=min_count).map(|i| share / i as f64).product::<f64>();
+            for count in min_count..=length - used {
+                next[used + count] += before * term;
+                term *= share / (count + 1) as f64;
+            }
+        }
+        terms = next;
+    }
+    let factorial = (1..=length).map(|i| i as f64).product::<f64>();
+    terms[length] * factorial
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    const SAMPLES: usize = 2_000;
+    const ASCII: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ1234567890!\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~";
+
+    fn samples(policy: &Policy, dictionary: &str, length: usize) -> Vec<Vec<char>> {
+        let mut rng = StdRng::seed_from_u64(21);
+        (0..SAMPLES)
+            .map(|_| {
+                policy
+                    .generate(dictionary, length, &mut rng)
+                    .unwrap()
+                    .chars()
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn minimum_counts_hold() {
+        let policy = Policy {
+            min_lowercase: 2,
+            min_uppercase: 3,
+            min_digits: 2,
+            min_symbols: 1,
+            ..Default::default()
+        };
+        for password in samples(&policy, ASCII, 12) {
+            assert_eq!(password.len(), 12);
+            for class in enum_iterator::all::<CharClass>() {
+                let count = password
+                    .iter()
+                    .filter(|c| CharClass::of(**c) == class)
+                    .count();
+                assert!(
+                    count >= policy.min_count(class),
+                    "{password:?} lacks {class}s"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn excluded_characters_never_appear() {
+        let policy = Policy {
+            excluded: "O0Il1|".to_string(),
+            ..Default::default()
+        };
+        for password in samples(&policy, ASCII, 16) {
+            assert!(
+                password.iter().all(|c| !policy.excluded.contains(*c)),
+                "{password:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn no_repeated_or_sequential_runs() {
+        let policy = Policy {
+            no_repeats: true,
+            no_sequences: true,
+            ..Default::default()
+        };
+        // Few characters, so runs would be common otherwise
+        for password in samples(&policy, "abcd123", 16) {
+            for run in password.windows(3) {
+                let (a, b, c) = (run[0] as i64, run[1] as i64, run[2] as i64);
+                assert!(!(a == b && b == c), "{password:?} repeats");
+                assert!(!(b - a == 1 && c - b == 1), "{password:?} goes up");
+                assert!(!(a - b == 1 && b - c == 1), "{password:?} goes down");
+            }
+        }
+    }
+
+    #[test]
+    fn first_character_has_the_required_class() {
+        for first in enum_iterator::all::<FirstCharacter>() {
+            let policy = Policy {
+                first,
+                ..Default::default()
+            };
+            for password in samples(&policy, ASCII, 8) {
+                assert!(
+                    first.allows(CharClass::of(password[0])),
+                    "{first:?}: {password:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn valid_passwords_are_equally_likely() {
+        // Of the 9 passwords of 2 characters of "aB1", the 5 with a digit
+        let policy = Policy {
+            min_digits: 1,
+            ..Default::default()
+        };
+        let mut rng = StdRng::seed_from_u64(21);
+        let draws = 50_000;
+        let mut counts = HashMap::<String, usize>::new();
+        for _ in 0..draws {
+            *counts
+                .entry(policy.generate("aB1", 2, &mut rng).unwrap())
+                .or_default() += 1;
+        }
+
+        assert_eq!(counts.len(), 5, "{counts:?}");
+        let expected = draws as f64 / 5.0;
+        let chi_square = counts
+            .values()
+            .map(|count| (*count as f64 - expected).powi(2) / expected)
+            .sum::<f64>();
+        // The 99.9th percentile of the chi-square distribution with 4 degrees
+        // of freedom
+        assert!(chi_square < 18.47, "{chi_square}: {counts:?}");
+    }
+
+    #[test]
+    fn impossible_policies_are_errors() {
+        let mut rng = StdRng::seed_from_u64(21);
+        let too_many = Policy {
+            min_digits: 3,
+            min_symbols: 3,
+            ..Default::default()
+        };
+        assert_eq!(
+            too_many.generate(ASCII, 5, &mut rng),
+            Err(PolicyError::TooShort {
+                required: 6,
+                length: 5
+            })
+        );
+
+        let no_digits = Policy {
+            min_digits: 1,
+            ..Default::default()
+        };
+        assert_eq!(
+            no_digits.generate("abc", 5, &mut rng),
+            Err(PolicyError::MissingClass(CharClass::Digit))
+        );
+
+        let all_excluded = Policy {
+            excluded: "abc".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            all_excluded.generate("abc", 5, &mut rng),
+            Err(PolicyError::NoCharacters)
+        );
+
+        let symbol_first = Policy {
+            first: FirstCharacter::Symbol,
+            ..Default::default()
+        };
+        assert_eq!(
+            symbol_first.generate("abc", 5, &mut rng),
+            Err(PolicyError::MissingClass(CharClass::Symbol))
+        );
+
+        let no_repeats = Policy {
+            no_repeats: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            no_repeats.generate("a", 5, &mut rng),
+            Err(PolicyError::SingleCharacter)
+        );
+        // Too short to have a run
+        assert_eq!(no_repeats.generate("a", 2, &mut rng).unwrap(), "aa");
+    }
+
+    #[test]
+    fn unlikely_policies_are_refused_without_drawing() {
+        /// Fails the test if a password is drawn.
+        struct NoDraws;
+        impl rand::RngCore for NoDraws {
+            fn next_u32(&mut self) -> u32 {
+                panic!("a password was drawn")
+            }
+            fn next_u64(&mut self) -> u64 {
+                panic!("a password was drawn")
+            }
+            fn fill_bytes(&mut self, _dest: &mut [u8]) {
+                panic!("a password was drawn")
+            }
+            fn try_fill_bytes(&mut self, _dest: &mut [u8]) -> Result<(), rand::Error> {
+                panic!("a password was drawn")
+            }
+        }
+
+        let every_class = Policy {
+            min_lowercase: 8,
+            min_uppercase: 8,
+            min_digits: 8,
+            min_symbols: 8,
+            ..Default::default()
+        };
+        assert_eq!(
+            every_class.generate(ASCII, 32, &mut NoDraws),
+            Err(PolicyError::TooStrict)
+        );
+        // Exactly 8 of each class, in any order
+        let orders = (1..=32).map(|i| i as f64).product::<f64>()
+            / (1..=8).map(|i| i as f64).product::<f64>().powi(4);
+        let expected = orders
+            * [26.0, 26.0, 10.0, 32.0]
+                .map(|count: f64| (count / 94.0).powi(8))
+                .iter()
+                .product::<f64>();
+        let acceptance = every_class.acceptance(&every_class.alphabet(ASCII), 32);
+        assert!(
+            (acceptance / expected - 1.0).abs() < 1e-9,
+            "{acceptance} {expected}"
+        );
+    }
+
+    #[test]
+    fn acceptance_estimates() {
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-9;
+        let alphabet = ['a', 'B', '1'];
+        assert!(close(Policy::default().acceptance(&alphabet, 8), 1.0));
+
+        // The 5 of the 9 passwords with a digit
+        let digit = Policy {
+            min_digits: 1,
+            ..Default::default()
+        };
+        assert!(close(digit.acceptance(&alphabet, 2), 5.0 / 9.0));
+        let two_digits = Policy {
+            min_digits: 2,
+            ..Default::default()
+        };
+        assert!(close(two_digits.acceptance(&alphabet, 2), 1.0 / 9.0));
+        let letter_first = Policy {
+            first: FirstCharacter::Letter,
+            ..Default::default()
+        };
+        assert!(close(letter_first.acceptance(&alphabet, 2), 2.0 / 3.0));
+
+        // 3 of the 27 passwords of 3 characters repeat one
+        let no_repeats = Policy {
+            no_repeats: true,
+            ..Default::default()
+        };
+        assert!(close(no_repeats.acceptance(&alphabet, 3), 24.0 / 27.0));
+    }
+
+    #[test]
+    fn multinomial_tails() {
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-9;
+        assert!(close(at_least_each(4, &[0.3, 0.7], &[0, 0]), 1.0));
+        assert!(close(at_least_each(4, &[0.5, 0.5], &[1, 0]), 15.0 / 16.0));
+        assert!(close(at_least_each(4, &[0.5, 0.5], &[4, 0]), 1.0 / 16.0));
+        assert!(close(at_least_each(4, &[0.5, 0.5], &[5, 0]), 0.0));
+        // Both at least twice is only ever two and two
+        assert!(close(at_least_each(4, &[0.5, 0.5], &[2, 2]), 6.0 / 16.0));
+        assert!(close(at_least_each(3, &[1.0, 0.0], &[2, 0]), 1.0));
+        assert!(close(at_least_each(3, &[0.0, 1.0], &[1, 0]), 0.0));
+    }
+
+    #[test]
+    fn runs() {
+        let password = |text: &str| text.chars().collect::<Vec<_>>();
+        let no_repeats = Policy {
+            no_repeats: true,
+            ..Default::default()
+        };
+        let no_sequences = Policy {
+            no_sequences: true,
+            ..Default::default()
+        };
+        assert!(!no_repeats.allows(&password("xaaay")));
+        assert!(no_repeats.allows(&password("xaayaa")));
+        assert!(no_repeats.allows(&password("abc")));
+        assert!(!no_sequences.allows(&password("xabcy")));
+        assert!(!no_sequences.allows(&password("x321y")));
+        assert!(no_sequences.allows(&password("aceabd")));
+        assert!(no_sequences.allows(&password("aaa")));
+    }
+}
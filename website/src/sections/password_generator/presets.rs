@@ -0,0 +1,391 @@
+//! Named generator settings, kept in `localStorage` and shared as JSON.
+
+use std::fmt;
+
+#[cfg(feature = "web")]
+use dioxus::logger::tracing::*;
+use serde::{Deserialize, Serialize};
+
+use super::passphrase::PassphraseOptions;
+use super::policy::{CharClass, Policy};
+use super::{Dictionary, Mode, DEFAULT_DICTIONARY, DEFAULT_MODE, DEFAULT_SIZE};
+use super::{MAX_CLASS_COUNT, MAX_SIZE, MAX_WORDS};
+
+/// What the preset picker shows when no preset is selected, so it can't name
+/// one.
+pub const NO_PRESET: &str = "None";
+/// The version of the exported JSON.
+const VERSION: u32 = 1;
+#[cfg(feature = "web")]
+const STORAGE_KEY: &str = "password-generator-presets";
+/// The query parameter selecting a preset by name.
+#[cfg(feature = "web")]
+const PRESET_PARAMETER: &str = "preset";
+
+/// Everything that decides how passwords are generated.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Preset {
+    pub name: String,
+    pub mode: Mode,
+    pub dictionary: Dictionary,
+    pub custom_dictionary: String,
+    /// Whether to NFC-normalize the custom dictionary.
+    pub normalize_unicode: bool,
+    /// Number of characters, in characters mode.
+    pub length: usize,
+    pub policy: Policy,
+    pub passphrase: PassphraseOptions,
+}
+
+impl Default for Preset {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            mode: DEFAULT_MODE,
+            dictionary: DEFAULT_DICTIONARY,
+            custom_dictionary: String::new(),
+            normalize_unicode: true,
+            length: DEFAULT_SIZE as usize,
+            policy: Policy::default(),
+            passphrase: PassphraseOptions::default(),
+        }
+    }
+}
+
+impl Preset {
+    /// Checks the preset has a name and settings the generator can show.
+    pub fn validate(&self) -> Result<(), PresetError> {
+        let name = self.name.trim();
+        if name.is_empty() {
+            return Err(PresetError::MissingName);
+        }
+        if name == NO_PRESET {
+            return Err(PresetError::ReservedName);
+        }
+
+        let out_of_range = |setting| PresetError::OutOfRange {
+            preset: self.name.clone(),
+            setting,
+        };
+        if !(1..=MAX_SIZE as usize).contains(&self.length) {
+            return Err(out_of_range("length"));
+        }
+        if !(1..=MAX_WORDS as usize).contains(&self.passphrase.words) {
+            return Err(out_of_range("passphrase.words"));
+        }
+        let classes = [
+            (CharClass::Lowercase, "policy.min_lowercase"),
+            (CharClass::Uppercase, "policy.min_uppercase"),
+            (CharClass::Digit, "policy.min_digits"),
+            (CharClass::Symbol, "policy.min_symbols"),
+        ];
+        for (class, setting) in classes {
+            if self.policy.min_count(class) > MAX_CLASS_COUNT as usize {
+                return Err(out_of_range(setting));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Why presets couldn't be imported or saved.
+#[derive(Debug)]
+pub enum PresetError {
+    /// The JSON is malformed or doesn't describe presets.
+    Json(serde_json::Error),
+    /// Exported by a newer version of the generator.
+    UnsupportedVersion(u32),
+    MissingName,
+    /// Named like the empty selection of the picker.
+    ReservedName,
+    /// Two presets in the same file share a name.
+    DuplicateName(String),
+    OutOfRange {
+        preset: String,
+        setting: &'static str,
+    },
+}
+
+impl fmt::Display for PresetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PresetError::Json(err) => write!(f, "The presets aren't valid: {err}"),
+            PresetError::UnsupportedVersion(version) => write!(
+                f,
+                "The presets are version {version}, but only version {VERSION} is supported"
+            ),
+            PresetError::MissingName => write!(f, "Presets need a name"),
+            PresetError::ReservedName => write!(f, "Presets can't be named \"{NO_PRESET}\""),
+            PresetError::DuplicateName(name) => {
+                write!(f, "There's more than one preset named \"{name}\"")
+            }
+            PresetError::OutOfRange { preset, setting } => {
+                write!(f, "The {setting} of \"{preset}\" is out of range")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PresetError {}
+
+impl From<serde_json::Error> for PresetError {
+    fn from(err: serde_json::Error) -> Self {
+        PresetError::Json(err)
+    }
+}
+
+/// The shared JSON, versioned in case the settings change.
+#[derive(Serialize, Deserialize)]
+struct PresetFile {
+    version: u32,
+    presets: Vec<Preset>,
+}
+
+pub fn to_json(presets: &[Preset]) -> String {
+    let file = PresetFile {
+        version: VERSION,
+        presets: presets.to_vec(),
+    };
+    serde_json::to_string_pretty(&file).expect("presets always serialize")
+}
+
+/// Reads presets exported by [`to_json`], checking every one of them.
+pub fn from_json(json: &str) -> Result<Vec<Preset>, PresetError> {
+    let file: PresetFile = serde_json::from_str(json)?;
+    if file.version != VERSION {
+        return Err(PresetError::UnsupportedVersion(file.version));
+    }
+
+    let mut presets: Vec<Preset> = Vec::with_capacity(file.presets.len());
+    for mut preset in file.presets {
+        preset.validate()?;
+        preset.name = preset.name.trim().to_string();
+        if presets.iter().any(|other| other.name == preset.name) {
+            return Err(PresetError::DuplicateName(preset.name));
+        }
+        presets.push(preset);
+    }
+    Ok(presets)
+}
+
+/// Adds `new` to `presets`, replacing the ones with the same name.
+pub fn merge(presets: &mut Vec<Preset>, new: impl IntoIterator<Item = Preset>) {
+    for preset in new {
+        match presets.iter_mut().find(|other| other.name == preset.name) {
+            Some(other) => *other = preset,
+            None => presets.push(preset),
+        }
+    }
+}
+
+#[cfg(feature = "web")]
+fn storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+/// The presets saved in this browser.
+#[cfg(feature = "web")]
+pub fn load() -> Vec<Preset> {
+    let Some(json) = storage().and_then(|storage| storage.get_item(STORAGE_KEY).ok()?) else {
+        return Vec::new();
+    };
+    from_json(&json).unwrap_or_else(|err| {
+        warn!("Ignoring the saved presets: {}", err);
+        Vec::new()
+    })
+}
+
+#[cfg(feature = "web")]
+pub fn store(presets: &[Preset]) {
+    let Some(storage) = storage() else {
+        warn!("There's no local storage to save the presets in");
+        return;
+    };
+    if let Err(err) = storage.set_item(STORAGE_KEY, &to_json(presets)) {
+        warn!("Failed to save the presets: {:?}", err);
+    }
+}
+
+#[cfg(not(feature = "web"))]
+pub fn store(_presets: &[Preset]) {}
+
+/// The preset the page was opened with, if any.
+#[cfg(feature = "web")]
+pub fn preset_from_url() -> Option<String> {
+    let search = web_sys::window()?.location().search().ok()?;
+    web_sys::UrlSearchParams::new_with_str(&search)
+        .ok()?
+        .get(PRESET_PARAMETER)
+}
+
+/// Keeps the address bar on a link to the `name` preset, or to no preset,
+/// without adding to the history.
+#[cfg(feature = "web")]
+pub fn update_url(name: Option<&str>) {
+    let Some(window) = web_sys::window() else {
+        return;
+    };
+    let location = window.location();
+    let (Ok(origin), Ok(pathname)) = (location.origin(), location.pathname()) else {
+        return;
+    };
+    let mut url = origin + &pathname;
+    if let Some(name) = name {
+        url += &format!(
+            "?{PRESET_PARAMETER}={}",
+            web_sys::js_sys::encode_uri_component(name)
+        );
+    }
+    if let Ok(history) = window.history() {
+        if let Err(err) =
+            history.replace_state_with_url(&wasm_bindgen::JsValue::NULL, "", Some(&url))
+        {
+            warn!("Failed to update the URL: {:?}", err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn preset(name: &str) -> Preset {
+        Preset {
+            name: name.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// The JSON of `presets`, with `edit` applied to it.
+    fn edited_json(presets: &[Preset], edit: impl FnOnce(&mut serde_json::Value)) -> String {
+        let mut json = serde_json::from_str(&to_json(presets)).unwrap();
+        edit(&mut json);
+        json.to_string()
+    }
+
+    #[test]
+    fn exported_presets_import_unchanged() {
+        let presets = vec![
+            preset("Defaults"),
+            Preset {
+                name: "Bank".to_string(),
+                mode: Mode::Characters,
+                dictionary: Dictionary::Custom,
+                custom_dictionary: "abc123".to_string(),
+                normalize_unicode: false,
+                length: 12,
+                policy: Policy {
+                    min_digits: 2,
+                    excluded: "0O".to_string(),
+                    no_repeats: true,
+                    ..Default::default()
+                },
+                passphrase: PassphraseOptions::default(),
+            },
+            Preset {
+                name: "Words".to_string(),
+                mode: Mode::Passphrase,
+                passphrase: PassphraseOptions {
+                    words: 4,
+                    separator: " ".to_string(),
+                    number: true,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        ];
+        assert_eq!(from_json(&to_json(&presets)).unwrap(), presets);
+        assert_eq!(from_json(&to_json(&[])).unwrap(), []);
+    }
+
+    #[test]
+    fn missing_settings_take_their_default() {
+        let presets =
+            from_json(r#"{"version": 1, "presets": [{"name": "Short", "length": 8}]}"#).unwrap();
+        assert_eq!(
+            presets,
+            [Preset {
+                length: 8,
+                ..preset("Short")
+            }]
+        );
+    }
+
+    #[test]
+    fn other_versions_are_rejected() {
+        let json = edited_json(&[preset("A")], |json| json["version"] = 2.into());
+        assert!(matches!(
+            from_json(&json),
+            Err(PresetError::UnsupportedVersion(2))
+        ));
+        assert!(matches!(
+            from_json(r#"{"presets": []}"#),
+            Err(PresetError::Json(_))
+        ));
+        assert!(matches!(from_json("[]"), Err(PresetError::Json(_))));
+    }
+
+    #[test]
+    fn out_of_range_settings_are_rejected() {
+        for (setting, value) in [("length", 0), ("length", MAX_SIZE as usize + 1)] {
+            let json = edited_json(&[preset("A")], |json| {
+                json["presets"][0][setting] = value.into()
+            });
+            assert_eq!(
+                from_json(&json).unwrap_err().to_string(),
+                "The length of \"A\" is out of range"
+            );
+        }
+        let json = edited_json(&[preset("A")], |json| {
+            json["presets"][0]["passphrase"]["words"] = (MAX_WORDS as usize + 1).into()
+        });
+        assert!(matches!(
+            from_json(&json),
+            Err(PresetError::OutOfRange {
+                setting: "passphrase.words",
+                ..
+            })
+        ));
+        let json = edited_json(&[preset("A")], |json| {
+            json["presets"][0]["policy"]["min_symbols"] = (MAX_CLASS_COUNT as usize + 1).into()
+        });
+        assert!(matches!(
+            from_json(&json),
+            Err(PresetError::OutOfRange {
+                setting: "policy.min_symbols",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn names_are_required_and_unique() {
+        for name in ["", "  "] {
+            assert!(matches!(
+                from_json(&to_json(&[preset(name)])),
+                Err(PresetError::MissingName)
+            ));
+        }
+        assert!(matches!(
+            from_json(&to_json(&[preset(NO_PRESET)])),
+            Err(PresetError::ReservedName)
+        ));
+        // Told apart once trimmed
+        assert!(matches!(
+            from_json(&to_json(&[preset("A"), preset("B"), preset(" A ")])),
+            Err(PresetError::DuplicateName(name)) if name == "A"
+        ));
+    }
+
+    #[test]
+    fn merging_replaces_presets_of_the_same_name() {
+        let mut presets = vec![preset("A"), preset("B")];
+        let new_a = Preset {
+            length: 8,
+            ..preset("A")
+        };
+        merge(&mut presets, [new_a.clone(), preset("C")]);
+        assert_eq!(presets, [new_a, preset("B"), preset("C")]);
+    }
+}
@@ -0,0 +1,586 @@
+//! How long a password would last against an attacker, in the spirit of
+//! zxcvbn: the password is split into the patterns people use (common
+//! passwords, repeats, sequences, keyboard walks and years) and brute-forced
+//! characters, and the cheapest split gives the number of guesses.
+//!
+//! The guesses are capped by the entropy of the generator, as an attacker who
+//! knows how the password was made can also just try every output.
+
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+/// Guesses per second of an offline attack against a fast hash, the scenario
+/// the crack time is given for.
+const GUESSES_PER_SECOND: f64 = 1e10;
+/// Years close to it are guessed first. It has to be bumped every year or
+/// so, or recent years will look stronger than they are.
+const REFERENCE_YEAR: i32 = 2026;
+/// How many years around [`REFERENCE_YEAR`] are guessed at least.
+const MIN_YEAR_SPACE: i32 = 20;
+/// Patterns shorter than this are left to brute force.
+const MIN_PATTERN_LENGTH: usize = 3;
+/// Guesses for each extra piece a password is split into, so splitting
+/// isn't free.
+const LOG10_PIECE_GUESSES: f64 = 4.0;
+
+/// The most common passwords and words in them, by rank.
+const COMMON_PASSWORDS: &[&str] = &[
+    "123456",
+    "password",
+    "123456789",
+    "12345678",
+    "12345",
+    "qwerty",
+    "1234567",
+    "111111",
+    "1234567890",
+    "123123",
+    "abc123",
+    "1234",
+    "password1",
+    "iloveyou",
+    "1q2w3e4r",
+    "000000",
+    "qwerty123",
+    "zaq12wsx",
+    "dragon",
+    "sunshine",
+    "princess",
+    "letmein",
+    "654321",
+    "monkey",
+    "27653",
+    "1qaz2wsx",
+    "123321",
+    "qwertyuiop",
+    "superman",
+    "asdfghjkl",
+    "football",
+    "secret",
+    "admin",
+    "welcome",
+    "login",
+    "master",
+    "hello",
+    "freedom",
+    "whatever",
+    "qazwsx",
+    "trustno1",
+    "baseball",
+    "shadow",
+    "michael",
+    "jordan",
+    "hunter",
+    "ashley",
+    "batman",
+    "starwars",
+    "passw0rd",
+    "mustang",
+    "access",
+    "flower",
+    "charlie",
+    "donald",
+    "love",
+    "loveme",
+    "hottie",
+    "killer",
+    "soccer",
+    "pokemon",
+    "jessica",
+    "summer",
+    "winter",
+    "spring",
+    "autumn",
+    "cookie",
+    "cheese",
+    "pepper",
+    "ginger",
+    "orange",
+    "banana",
+    "apple",
+    "chocolate",
+    "purple",
+    "silver",
+    "golden",
+    "tigger",
+    "buster",
+    "daniel",
+    "andrew",
+    "thomas",
+    "robert",
+    "matrix",
+    "ninja",
+    "azerty",
+    "solo",
+    "computer",
+    "internet",
+    "google",
+    "samsung",
+    "iphone",
+    "test",
+    "guest",
+    "root",
+    "user",
+    "default",
+    "changeme",
+    "contraseña",
+    "hola",
+    "amor",
+    "casa",
+    "perro",
+    "gato",
+    "barcelona",
+    "madrid",
+    "real",
+    "futbol",
+];
+
+/// Rows of a QWERTY keyboard, for keyboard walks.
+const KEYBOARD_ROWS: &[&str] = &[
+    "`1234567890-=",
+    "qwertyuiop[]\\",
+    "asdfghjkl;'",
+    "zxcvbnm,./",
+];
+/// How many keys a walk can start on, and how many neighbours a key has on
+/// average, counting the rows above and below.
+const KEYBOARD_STARTS: f64 = 47.0;
+const KEYBOARD_DEGREE: f64 = 4.6;
+
+static COMMON_RANKS: LazyLock<HashMap<&'static str, usize>> = LazyLock::new(|| {
+    COMMON_PASSWORDS
+        .iter()
+        .enumerate()
+        .map(|(rank, word)| (*word, rank + 1))
+        .collect()
+});
+
+/// A part of the password, `start..end` in characters, that takes
+/// `10^log10_guesses` guesses to find.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Piece {
+    start: usize,
+    end: usize,
+    log10_guesses: f64,
+}
+
+/// `log10(n choose k)`.
+fn log10_binomial(n: usize, k: usize) -> f64 {
+    (0..k)
+        .map(|i| ((n - i) as f64 / (i + 1) as f64).log10())
+        .sum()
+}
+
+/// `log10(10^a + 10^b)`, without overflowing.
+fn log10_add(a: f64, b: f64) -> f64 {
+    let (high, low) = if a > b { (a, b) } else { (b, a) };
+    high + (1.0 + 10f64.powf(low - high)).log10()
+}
+
+/// How many characters the classes of `chars` have, which brute force has to
+/// go through.
+fn bruteforce_cardinality(chars: &[char]) -> f64 {
+    let has = |test: fn(&char) -> bool| chars.iter().any(test);
+    let mut cardinality = 0.0;
+    if has(char::is_ascii_lowercase) {
+        cardinality += 26.0;
+    }
+    if has(char::is_ascii_uppercase) {
+        cardinality += 26.0;
+    }
+    if has(char::is_ascii_digit) {
+        cardinality += 10.0;
+    }
+    if has(char::is_ascii_punctuation) || has(|c| *c == ' ') {
+        cardinality += 33.0;
+    }
+    if has(|c| !c.is_ascii()) {
+        cardinality += 100.0;
+    }
+    cardinality
+}
+
+/// The extra guesses for the capitalizations of a word, the usual ones first.
+fn log10_uppercase_variations(word: &[char]) -> f64 {
+    let upper = word.iter().filter(|c| c.is_uppercase()).count();
+    let lower = word.iter().filter(|c| c.is_lowercase()).count();
+    if upper == 0 {
+        return 0.0;
+    }
+    let first_only = upper == 1 && word[0].is_uppercase();
+    let last_only = upper == 1 && word[word.len() - 1].is_uppercase();
+    if lower == 0 || first_only || last_only {
+        return 2f64.log10();
+    }
+    (1..=upper.min(lower))
+        .map(|i| log10_binomial(upper + lower, i))
+        .fold(f64::NEG_INFINITY, log10_add)
+}
+
+fn common_password_pieces(chars: &[char], pieces: &mut Vec<Piece>) {
+    for start in 0..chars.len() {
+        for end in start + MIN_PATTERN_LENGTH..=chars.len() {
+            let word = &chars[start..end];
+            let lower = word
+                .iter()
+                .flat_map(|c| c.to_lowercase())
+                .collect::<String>();
+            let reversed = lower.chars().rev().collect::<String>();
+            let found = match (
+                COMMON_RANKS.get(lower.as_str()),
+                COMMON_RANKS.get(reversed.as_str()),
+            ) {
+                (Some(&rank), _) => Some(rank as f64),
+                (None, Some(&rank)) => Some(rank as f64 * 2.0),
+                (None, None) => None,
+            };
+            if let Some(guesses) = found {
+                pieces.push(Piece {
+                    start,
+                    end,
+                    log10_guesses: guesses.log10() + log10_uppercase_variations(word),
+                });
+            }
+        }
+    }
+}
+
+/// A character repeated, or a block of them, such as `aaaa` or `abcabc`.
+/// Only the shortest block repeated from each position is taken, which keeps
+/// the recursion on the block cheap.
+fn repeat_pieces(chars: &[char], pieces: &mut Vec<Piece>) {
+    for start in 0..chars.len() {
+        for period in 1..=(chars.len() - start) / 2 {
+            let block = &chars[start..start + period];
+            let repeats = chars[start..]
+                .chunks_exact(period)
+                .take_while(|chunk| *chunk == block)
+                .count();
+            if repeats < 2 || period * repeats < MIN_PATTERN_LENGTH {
+                continue;
+            }
+            pieces.push(Piece {
+                start,
+                end: start + period * repeats,
+                log10_guesses: most_guessable(block) + (repeats as f64).log10(),
+            });
+            break;
+        }
+    }
+}
+
+/// Characters with the same small step between them, such as `abcd`, `9753`.
+fn sequence_pieces(chars: &[char], pieces: &mut Vec<Piece>) {
+    let mut start = 0;
+    while start + 1 < chars.len() {
+        let delta = chars[start + 1] as i64 - chars[start] as i64;
+        let mut end = start + 2;
+        while end < chars.len() && chars[end] as i64 - chars[end - 1] as i64 == delta {
+            end += 1;
+        }
+        if (1..=5).contains(&delta.abs()) && end - start >= MIN_PATTERN_LENGTH {
+            let first = chars[start];
+            let base: f64 = if "aAzZ019".contains(first) {
+                4.0
+            } else if first.is_ascii_digit() {
+                10.0
+            } else {
+                26.0
+            };
+            let direction: f64 = if delta < 0 { 2.0 } else { 1.0 };
+            pieces.push(Piece {
+                start,
+                end,
+                log10_guesses: (base * (end - start) as f64 * direction).log10(),
+            });
+        }
+        start = end - 1;
+    }
+}
+
+/// Where `c` is on the keyboard, as row and column.
+fn key_position(c: char) -> Option<(usize, usize)> {
+    let c = c.to_ascii_lowercase();
+    KEYBOARD_ROWS
+        .iter()
+        .enumerate()
+        .find_map(|(row, keys)| keys.chars().position(|key| key == c).map(|col| (row, col)))
+}
+
+/// Keys next to each other, such as `qwerty`, `asdfdsa` or `1qaz`.
+fn keyboard_pieces(chars: &[char], pieces: &mut Vec<Piece>) {
+    let positions = chars.iter().map(|c| key_position(*c)).collect::<Vec<_>>();
+    let step = |i: usize| -> Option<(isize, isize)> {
+        let (row, col) = positions[i]?;
+        let (next_row, next_col) = positions[i + 1]?;
+        let step = (
+            next_row as isize - row as isize,
+            next_col as isize - col as isize,
+        );
+        (step.0.abs() <= 1 && step.1.abs() <= 1 && step != (0, 0)).then_some(step)
+    };
+
+    let mut start = 0;
+    while start < chars.len() {
+        let mut end = start + 1;
+        let mut turns = 0;
+        let mut direction = None;
+        while end < chars.len() {
+            let Some(step) = step(end - 1) else {
+                break;
+            };
+            if direction != Some(step) {
+                turns += 1;
+                direction = Some(step);
+            }
+            end += 1;
+        }
+        let length = end - start;
+        if length >= MIN_PATTERN_LENGTH {
+            // Every way of making `turns` turns in walks up to this long
+            let guesses = (2..=length)
+                .flat_map(|i| (1..=turns.min(i - 1)).map(move |j| (i, j)))
+                .map(|(i, j)| {
+                    log10_binomial(i - 1, j - 1)
+                        + KEYBOARD_STARTS.log10()
+                        + j as f64 * KEYBOARD_DEGREE.log10()
+                })
+                .fold(f64::NEG_INFINITY, log10_add);
+            pieces.push(Piece {
+                start,
+                end,
+                log10_guesses: guesses + log10_uppercase_variations(&chars[start..end]),
+            });
+        }
+        start = end;
+    }
+}
+
+/// Four digit years, the recent ones being guessed first.
+fn year_pieces(chars: &[char], pieces: &mut Vec<Piece>) {
+    for start in 0..chars.len().saturating_sub(3) {
+        let digits = &chars[start..start + 4];
+        if !digits.iter().all(char::is_ascii_digit) {
+            continue;
+        }
+        let year = digits
+            .iter()
+            .collect::<String>()
+            .parse::<i32>()
+            .unwrap_or(0);
+        if (1900..=2099).contains(&year) {
+            let space = (year - REFERENCE_YEAR).abs().max(MIN_YEAR_SPACE);
+            pieces.push(Piece {
+                start,
+                end: start + 4,
+                log10_guesses: (space as f64).log10(),
+            });
+        }
+    }
+}
+
+/// `log10` of the guesses to find `chars` with the cheapest split into
+/// patterns and brute force.
+fn most_guessable(chars: &[char]) -> f64 {
+    let length = chars.len();
+    if length == 0 {
+        return 0.0;
+    }
+
+    let mut pieces = vec![];
+    common_password_pieces(chars, &mut pieces);
+    repeat_pieces(chars, &mut pieces);
+    sequence_pieces(chars, &mut pieces);
+    keyboard_pieces(chars, &mut pieces);
+    year_pieces(chars, &mut pieces);
+    for start in 0..length {
+        for end in start + 1..=length {
+            let segment = &chars[start..end];
+            pieces.push(Piece {
+                start,
+                end,
+                log10_guesses: segment.len() as f64 * bruteforce_cardinality(segment).log10(),
+            });
+        }
+    }
+
+    // best[count][end]: the fewest guesses for `chars[..end]` in `count` pieces
+    let mut best = vec![vec![f64::INFINITY; length + 1]; length + 1];
+    best[0][0] = 0.0;
+    for count in 1..=length {
+        for piece in &pieces {
+            let before = best[count - 1][piece.start];
+            if before + piece.log10_guesses < best[count][piece.end] {
+                best[count][piece.end] = before + piece.log10_guesses;
+            }
+        }
+    }
+
+    (1..=length)
+        .filter(|&count| best[count][length].is_finite())
+        .map(|count| {
+            // The pieces can come in any order, and their number isn't known
+            let orderings = (1..=count).map(|i| (i as f64).log10()).sum::<f64>();
+            log10_add(
+                orderings + best[count][length],
+                (count - 1) as f64 * LOG10_PIECE_GUESSES,
+            )
+        })
+        .fold(f64::INFINITY, f64::min)
+}
+
+/// How strong a password is.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Strength {
+    /// `log10` of the guesses an attacker needs.
+    pub log10_guesses: f64,
+}
+
+impl Strength {
+    /// The strength of `password`, which a generator with `entropy_bits` of
+    /// entropy made.
+    pub fn new(password: &str, entropy_bits: f64) -> Self {
+        let chars = password.chars().collect::<Vec<_>>();
+        Self {
+            log10_guesses: most_guessable(&chars).min(entropy_bits * 2f64.log10()),
+        }
+    }
+
+    /// From 0, guessed right away, to 4, out of reach, as zxcvbn scores them.
+    pub fn score(&self) -> u8 {
+        match self.log10_guesses {
+            guesses if guesses < 3.0 => 0,
+            guesses if guesses < 6.0 => 1,
+            guesses if guesses < 8.0 => 2,
+            guesses if guesses < 10.0 => 3,
+            _ => 4,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        ["Very weak", "Weak", "Fair", "Strong", "Very strong"][self.score() as usize]
+    }
+
+    /// How long an offline attack against a fast hash takes, in words.
+    pub fn crack_time(&self) -> String {
+        const MINUTE: f64 = 60.0;
+        const HOUR: f64 = 60.0 * MINUTE;
+        const DAY: f64 = 24.0 * HOUR;
+        const MONTH: f64 = 31.0 * DAY;
+        const YEAR: f64 = 12.0 * MONTH;
+        const CENTURY: f64 = 100.0 * YEAR;
+
+        let log10_seconds = self.log10_guesses - GUESSES_PER_SECOND.log10();
+        if log10_seconds >= CENTURY.log10() {
+            return "centuries".to_string();
+        }
+        let seconds = 10f64.powf(log10_seconds);
+        let (amount, unit) = match seconds {
+            seconds if seconds < 1.0 => return "less than a second".to_string(),
+            seconds if seconds < MINUTE => (seconds, "second"),
+            seconds if seconds < HOUR => (seconds / MINUTE, "minute"),
+            seconds if seconds < DAY => (seconds / HOUR, "hour"),
+            seconds if seconds < MONTH => (seconds / DAY, "day"),
+            seconds if seconds < YEAR => (seconds / MONTH, "month"),
+            seconds => (seconds / YEAR, "year"),
+        };
+        let amount = amount.round() as u64;
+        format!("{amount} {unit}{}", if amount == 1 { "" } else { "s" })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// More than any password here has, so only the patterns count.
+    const ENTROPY: f64 = 1000.0;
+
+    fn strength(password: &str) -> Strength {
+        Strength::new(password, ENTROPY)
+    }
+
+    /// The strength of a password that takes `seconds` to crack.
+    fn cracked_in(seconds: f64) -> Strength {
+        Strength {
+            log10_guesses: GUESSES_PER_SECOND.log10() + seconds.log10(),
+        }
+    }
+
+    #[test]
+    fn common_patterns_are_weak() {
+        for password in [
+            "password", "Password", "qwerty", "123456", "asdfgh", "abcdef", "aaaaaaa", "19901231",
+            "1990", "drowssap",
+        ] {
+            let strength = strength(password);
+            assert!(strength.score() <= 1, "{password}: {strength:?}");
+            assert_eq!(strength.crack_time(), "less than a second", "{password}");
+        }
+    }
+
+    #[test]
+    fn patterns_are_cheaper_than_brute_force() {
+        let random = strength("pwsorad").log10_guesses;
+        assert!(strength("password").log10_guesses < random - 3.0);
+        assert!(strength("P4ssword2025").log10_guesses < strength("P4ssword7391").log10_guesses);
+    }
+
+    #[test]
+    fn random_passwords_and_passphrases_are_strong() {
+        for password in [
+            "x7#Qp9!vLm2@Rt8$Wz",
+            "Tq4vN8zK2mWp6sLc",
+            "correct-horse-battery-staple",
+            "lantern orbit velvet quarry mosaic",
+        ] {
+            let strength = strength(password);
+            assert_eq!(strength.score(), 4, "{password}: {strength:?}");
+            assert_eq!(strength.crack_time(), "centuries", "{password}");
+        }
+    }
+
+    #[test]
+    fn guesses_are_capped_by_the_entropy_of_the_generator() {
+        let strength = Strength::new("x7#Qp9!vLm2@Rt8$Wz", 20.0);
+        assert!((strength.log10_guesses - 20.0 * 2f64.log10()).abs() < 1e-9);
+        assert_eq!(strength.score(), 2);
+    }
+
+    #[test]
+    fn scores() {
+        let score = |log10_guesses| Strength { log10_guesses }.score();
+        assert_eq!(score(0.0), 0);
+        assert_eq!(score(2.99), 0);
+        assert_eq!(score(3.0), 1);
+        assert_eq!(score(5.99), 1);
+        assert_eq!(score(6.0), 2);
+        assert_eq!(score(7.99), 2);
+        assert_eq!(score(8.0), 3);
+        assert_eq!(score(9.99), 3);
+        assert_eq!(score(10.0), 4);
+        assert_eq!(Strength { log10_guesses: 8.0 }.label(), "Strong");
+    }
+
+    #[test]
+    fn crack_times() {
+        let cases = [
+            (0.99, "less than a second"),
+            (1.01, "1 second"),
+            (59.0, "59 seconds"),
+            (60.01, "1 minute"),
+            (90.0, "2 minutes"),
+            (3600.01, "1 hour"),
+            (86400.01, "1 day"),
+            (30.0 * 86400.0, "30 days"),
+            (31.01 * 86400.0, "1 month"),
+            (11.0 * 31.0 * 86400.0, "11 months"),
+            (12.01 * 31.0 * 86400.0, "1 year"),
+            (99.0 * 12.0 * 31.0 * 86400.0, "99 years"),
+            (100.01 * 12.0 * 31.0 * 86400.0, "centuries"),
+        ];
+        for (seconds, expected) in cases {
+            assert_eq!(cracked_in(seconds).crack_time(), expected, "{seconds}s");
+        }
+    }
+}
@@ -0,0 +1,293 @@
+use dioxus::logger::tracing::*;
+use dioxus::prelude::*;
+
+use crate::sections::blog::{entries::AspectRatio, DemoCanvas};
+#[cfg(feature = "web")]
+use crate::utils::wasm_loader::{DemoHandle, WasmModule};
+
+/// The fragment shader the playground starts with, which is also the one the
+/// `playground` scene of `triangle-demo` starts with.
+const DEFAULT_SHADER: &str = include_str!("../../../triangle-demo/src/playground.wgsl");
+/// The demo module and scene running the shader.
+#[cfg(feature = "web")]
+const MODULE: &str = "triangle-demo";
+#[cfg(feature = "web")]
+const SCENE: &str = "playground";
+const CANVAS_ID: &str = "shader-playground";
+const ASPECT_RATIO: AspectRatio = AspectRatio {
+    width: 4,
+    height: 3,
+};
+/// The query parameter of shared links holding the shader.
+#[cfg(feature = "web")]
+const SHADER_PARAMETER: &str = "shader";
+/// How long typing has to stop for, in milliseconds, before the shader is
+/// compiled and put in the URL.
+#[cfg(feature = "web")]
+const DEBOUNCE_MS: i32 = 300;
+
+/// A problem the demo found in the shader.
+#[derive(Clone, Debug, PartialEq)]
+struct Diagnostic {
+    line: Option<u32>,
+    column: Option<u32>,
+    message: String,
+}
+
+/// The shader of the link the page was opened with, if any.
+#[cfg(feature = "web")]
+fn shader_from_url() -> Option<String> {
+    let search = web_sys::window()?.location().search().ok()?;
+    web_sys::UrlSearchParams::new_with_str(&search)
+        .ok()?
+        .get(SHADER_PARAMETER)
+}
+
+/// A link to the playground opening `source`.
+#[cfg(feature = "web")]
+fn share_url(source: &str) -> Option<String> {
+    let location = web_sys::window()?.location();
+    let mut url = location.origin().ok()? + &location.pathname().ok()?;
+    if source != DEFAULT_SHADER {
+        url += &format!(
+            "?{SHADER_PARAMETER}={}",
+            web_sys::js_sys::encode_uri_component(source)
+        );
+    }
+    Some(url)
+}
+
+/// Keeps the address bar on a link to `source`, without adding to the history.
+#[cfg(feature = "web")]
+fn update_url(source: &str) {
+    let Some(url) = share_url(source) else {
+        return;
+    };
+    let history = web_sys::window().and_then(|window| window.history().ok());
+    if let Some(history) = history {
+        if let Err(err) =
+            history.replace_state_with_url(&wasm_bindgen::JsValue::NULL, "", Some(&url))
+        {
+            warn!("Failed to update the URL: {:?}", err);
+        }
+    }
+}
+
+#[component]
+fn ShaderEditor(source: Signal<String>, diagnostics: ReadOnlySignal<Vec<Diagnostic>>) -> Element {
+    let line_count =
+        source.read().lines().count().max(1) + usize::from(source.read().ends_with('\n'));
+    let error_lines = diagnostics
+        .read()
+        .iter()
+        .filter_map(|diagnostic| diagnostic.line)
+        .collect::<Vec<_>>();
+
+    rsx! {
+        div { class: "shader-editor",
+            div { class: "shader-gutter", aria_hidden: "true",
+                for line in 1..=line_count {
+                    div {
+                        key: "{line}",
+                        class: if error_lines.contains(&(line as u32)) { "shader-gutter-error" },
+                        "{line}"
+                    }
+                }
+            }
+            textarea {
+                class: "shader-source",
+                id: "shader-source",
+                aria_label: "Fragment shader",
+                spellcheck: "false",
+                autocomplete: "off",
+                wrap: "off",
+                rows: "{line_count}",
+                value: "{source}",
+                oninput: move |event| source.set(event.value()),
+            }
+        }
+        if !diagnostics.read().is_empty() {
+            ul { class: "shader-diagnostics", role: "alert",
+                for diagnostic in diagnostics.read().iter() {
+                    li {
+                        match (diagnostic.line, diagnostic.column) {
+                            (Some(line), Some(column)) => format!("Line {line}:{column}: "),
+                            (Some(line), None) => format!("Line {line}: "),
+                            _ => String::new(),
+                        }
+                        {diagnostic.message.clone()}
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+pub fn ShaderPlayground() -> Element {
+    info!("Creating Shader Playground");
+
+    let mut source = use_signal(|| DEFAULT_SHADER.to_string());
+    #[allow(unused_mut)]
+    let mut diagnostics = use_signal(Vec::<Diagnostic>::new);
+    #[allow(unused_mut)]
+    let mut errorMessage = use_signal(|| Option::<String>::None);
+    #[allow(unused_mut)]
+    let mut backend = use_signal(|| Option::<String>::None);
+    #[allow(unused_mut)]
+    let mut copied = use_signal(|| false);
+
+    #[cfg(feature = "web")]
+    let mut handle = use_signal(|| Option::<DemoHandle>::None);
+    // The compile waiting for typing to stop
+    #[cfg(feature = "web")]
+    let mut pending = use_signal(|| Option::<Task>::None);
+    // Not only a signal, as those are already gone when `use_drop` runs
+    #[cfg(feature = "web")]
+    let running = use_hook(|| std::rc::Rc::new(std::cell::RefCell::new(None::<DemoHandle>)));
+    #[cfg(feature = "web")]
+    use_drop({
+        let running = running.clone();
+        move || {
+            if let Some(handle) = running.borrow_mut().take() {
+                if let Err(err) = handle.stop() {
+                    warn!("Error: {}", err);
+                }
+            }
+        }
+    });
+
+    // Read after hydration, as the pre-rendered page has the default shader
+    #[cfg(feature = "web")]
+    use_effect(move || {
+        if let Some(shared) = shader_from_url() {
+            source.set(shared);
+        }
+    });
+
+    // Recompile whenever the shader changes or the demo starts, once typing
+    // stops for a moment
+    #[cfg(feature = "web")]
+    use_effect(move || {
+        let source = source();
+        let handle = handle();
+        copied.set(false);
+        if let Some(task) = pending.take() {
+            task.cancel();
+        }
+        pending.set(Some(spawn(async move {
+            crate::utils::timer::sleep(DEBOUNCE_MS).await;
+            pending.set(None);
+            update_url(&source);
+            let Some(handle) = handle else {
+                return;
+            };
+            match handle.set_shader(&source).await {
+                Ok(problems) => diagnostics.set(
+                    problems
+                        .into_iter()
+                        .map(|problem| Diagnostic {
+                            line: problem.line,
+                            column: problem.column,
+                            message: problem.message,
+                        })
+                        .collect(),
+                ),
+                // Usually a newer version of the shader replaced this one
+                Err(err) => debug!("Shader not applied: {}", err),
+            }
+        })));
+    });
+
+    #[allow(unused_variables)]
+    let mountedFn = move |event: dioxus::prelude::Event<MountedData>| {
+        #[cfg(feature = "web")]
+        let running = running.clone();
+        async move {
+            #[cfg(feature = "web")]
+            {
+                let result = async {
+                    let module = WasmModule::load(&WasmModule::script_url(MODULE)).await?;
+                    let started = module.run(CANVAS_ID, Some(SCENE)).await?;
+                    backend.set(started.backend());
+                    *running.borrow_mut() = Some(started.clone());
+                    handle.set(Some(started));
+                    dioxus::Result::Ok(())
+                }
+                .await;
+                if let Err(err) = result {
+                    warn!("Error: {}", err);
+                    errorMessage.set(Some(format!("{}", err)));
+                }
+            }
+        }
+    };
+
+    rsx! {
+        div { class: "relative bg-cover bg-center bg-no-repeat py-8 md:py-4 lg:py-8",
+            div { class: "absolute inset-0 z-20 bg-gradient-to-r from-hero-gradient-from to-hero-gradient-to bg-cover bg-center bg-no-repeat" }
+            div { class: "container relative z-30 pt-2 sm:pt-5 md:pt-0 lg:pt-8",
+                div { class: "flex flex-col items-center justify-center lg:flex-row",
+                    div { class: "pt-8 sm:pt-10 lg:pl-8 md:pt-0 lg:pt-0",
+                        h1 { class: "text-center font-header text-2xl text-white-text sm:text-left sm:text-3xl md:text-3xl",
+                            "Live WGSL Shader Playground"
+                        }
+                    }
+                }
+            }
+        }
+        section { class: "shader-playground",
+            div {
+                label {
+                    r#for: "shader-source",
+                    class: "text-white-text",
+                    "Fragment shader"
+                }
+                div { class: "mt-4",
+                    ShaderEditor { source, diagnostics }
+                }
+            }
+            div {
+                if let Some(msg) = errorMessage() {
+                    p { class: "text-center text-white-text",
+                        "The playground needs a browser with WebGPU or WebGL2 support."
+                    }
+                    p { class: "text-center text-error", {msg} }
+                } else {
+                    DemoCanvas {
+                        id: CANVAS_ID,
+                        aspect_ratio: ASPECT_RATIO,
+                        onmounted: mountedFn,
+                    }
+                    if let Some(backend) = backend() {
+                        p { class: "text-center text-sm text-grey-20 pt-2", "Rendered with {backend}" }
+                    }
+                }
+                div { class: "grid grid-cols-2 gap-6 mt-4",
+                    button {
+                        r#type: "button",
+                        onclick: move |_| async move {
+                            #[cfg(feature = "web")]
+                            if let Some(url) = share_url(&source()) {
+                                crate::utils::clipboard::set_clipboard(url).await;
+                                copied.set(true);
+                            }
+                        },
+                        class: "px-6 py-2 w-full leading-5 text-white-text transition-colors duration-200 transform bg-tertiary rounded-md hover:bg-quaternary focus:outline-none focus:bg-gray-600",
+                        if copied() {
+                            "Link copied"
+                        } else {
+                            "Copy link"
+                        }
+                    }
+                    button {
+                        r#type: "button",
+                        onclick: move |_| source.set(DEFAULT_SHADER.to_string()),
+                        class: "px-6 py-2 w-full leading-5 text-white-text transition-colors duration-200 transform bg-tertiary rounded-md hover:bg-quaternary focus:outline-none focus:bg-gray-600",
+                        "Reset"
+                    }
+                }
+            }
+        }
+    }
+}
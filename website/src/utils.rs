@@ -1 +1,5 @@
 pub mod clipboard;
+pub mod download;
+pub mod timer;
+#[cfg(feature = "web")]
+pub mod wasm_loader;
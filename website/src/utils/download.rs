@@ -0,0 +1,50 @@
+/// How long the object URL of a download is kept, in milliseconds.
+#[cfg(feature = "web")]
+const REVOKE_DELAY: i32 = 40_000;
+
+/// Makes the browser save `contents` as `file_name`, without a server.
+#[cfg(feature = "web")]
+pub fn download(file_name: &str, mime_type: &str, contents: &str) {
+    use dioxus::logger::tracing::*;
+    use wasm_bindgen::JsCast;
+
+    let result = (|| {
+        let document = web_sys::window()
+            .and_then(|window| window.document())
+            .ok_or("there's no document")?;
+        let options = web_sys::BlobPropertyBag::new();
+        options.set_type(mime_type);
+        let parts = web_sys::js_sys::Array::of1(&contents.into());
+        let blob = web_sys::Blob::new_with_str_sequence_and_options(&parts, &options)?;
+        let url = web_sys::Url::create_object_url_with_blob(&blob)?;
+
+        let link = document
+            .create_element("a")?
+            .dyn_into::<web_sys::HtmlAnchorElement>()?;
+        link.set_href(&url);
+        link.set_download(file_name);
+        // Some browsers ignore clicks on links that aren't in the page
+        let body = document.body().ok_or("there's no body")?;
+        body.append_child(&link)?;
+        link.click();
+        link.remove();
+
+        // The download reads the URL after the click returns
+        let revoke = wasm_bindgen::closure::Closure::once_into_js(move || {
+            let _ = web_sys::Url::revoke_object_url(&url);
+        });
+        web_sys::window()
+            .ok_or("there's no window")?
+            .set_timeout_with_callback_and_timeout_and_arguments_0(
+                revoke.unchecked_ref(),
+                REVOKE_DELAY,
+            )?;
+        Ok::<(), wasm_bindgen::JsValue>(())
+    })();
+    if let Err(err) = result {
+        error!("Failed to download {}: {:?}", file_name, err);
+    }
+}
+
+#[cfg(not(feature = "web"))]
+pub fn download(_file_name: &str, _mime_type: &str, _contents: &str) {}
@@ -0,0 +1,14 @@
+/// Resolves after `ms` milliseconds, letting the page render and handle events
+/// meanwhile.
+#[cfg(feature = "web")]
+pub async fn sleep(ms: i32) {
+    let promise = web_sys::js_sys::Promise::new(&mut |resolve, _| {
+        if let Some(window) = web_sys::window() {
+            let _ = window.set_timeout_with_callback_and_timeout_and_arguments_0(&resolve, ms);
+        }
+    });
+    let _ = wasm_bindgen_futures::JsFuture::from(promise).await;
+}
+
+#[cfg(not(feature = "web"))]
+pub async fn sleep(_ms: i32) {}
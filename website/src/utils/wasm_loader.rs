@@ -0,0 +1,322 @@
+//! Loading of the wasm-bindgen modules (built with `--target web`) that the
+//! blog embeds as demos.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+
+use futures_util::future::{FutureExt, LocalBoxFuture, Shared};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::js_sys;
+
+// A real module rather than an eval'd string, so it works under a strict CSP.
+#[wasm_bindgen(inline_js = "export function import_module(url) { return import(url); }")]
+extern "C" {
+    #[wasm_bindgen(catch)]
+    fn import_module(url: &str) -> Result<js_sys::Promise, JsValue>;
+}
+
+/// Why a module couldn't be loaded or run.
+#[derive(Clone, Debug, PartialEq)]
+pub enum LoadError {
+    /// The module couldn't be fetched or isn't valid JS.
+    Import { url: String, message: String },
+    /// The module doesn't export a function the loader needs.
+    MissingExport { url: String, export: &'static str },
+    /// The `init` (default) export failed, such as when the wasm is missing.
+    Init { url: String, message: String },
+    /// The `run` export failed.
+    Run { url: String, message: String },
+    /// A method of the handle returned by `run` is missing or failed.
+    Control {
+        url: String,
+        method: &'static str,
+        message: String,
+    },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Import { url, message } => write!(f, "failed to import {url}: {message}"),
+            LoadError::MissingExport { url, export } => {
+                write!(f, "{url} doesn't export a `{export}` function")
+            }
+            LoadError::Init { url, message } => {
+                write!(f, "failed to initialize {url}: {message}")
+            }
+            LoadError::Run { url, message } => write!(f, "{url} failed to run: {message}"),
+            LoadError::Control {
+                url,
+                method,
+                message,
+            } => write!(f, "failed to {method} {url}: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+/// Turns a thrown JS value into something readable.
+fn describe(value: &JsValue) -> String {
+    if let Some(message) = value.as_string() {
+        message
+    } else if let Some(error) = value.dyn_ref::<js_sys::Error>() {
+        error.message().into()
+    } else {
+        format!("{value:?}")
+    }
+}
+
+/// Awaits `value` if it's a promise, as exports may be sync or async.
+async fn settle(value: JsValue) -> Result<JsValue, JsValue> {
+    match value.dyn_into::<js_sys::Promise>() {
+        Ok(promise) => JsFuture::from(promise).await,
+        Err(value) => Ok(value),
+    }
+}
+
+type Loading = Shared<LocalBoxFuture<'static, Result<WasmModule, LoadError>>>;
+
+thread_local! {
+    /// Modules by URL, so each one is only instantiated once per page load
+    /// even if several demos ask for it at the same time.
+    static MODULES: RefCell<HashMap<String, Loading>> = RefCell::default();
+}
+
+/// An imported and initialized wasm-bindgen module.
+#[derive(Clone, Debug)]
+pub struct WasmModule {
+    url: String,
+    exports: js_sys::Object,
+}
+
+impl WasmModule {
+    /// The JS glue wasm-pack generates for `module`.
+    pub fn script_url(module: &str) -> String {
+        format!("/assets/wasm/{module}/{}.js", module.replace('-', "_"))
+    }
+
+    /// Imports the JS glue at `url` and runs its `init`, or returns the module
+    /// if that was already done.
+    pub async fn load(url: &str) -> Result<WasmModule, LoadError> {
+        let loading = MODULES.with_borrow_mut(|modules| {
+            modules
+                .entry(url.to_string())
+                .or_insert_with(|| Self::instantiate(url.to_string()).boxed_local().shared())
+                .clone()
+        });
+
+        let module = loading.await;
+        if module.is_err() {
+            // Let a later attempt try again
+            MODULES.with_borrow_mut(|modules| modules.remove(url));
+        }
+        module
+    }
+
+    async fn instantiate(url: String) -> Result<WasmModule, LoadError> {
+        let import_error = |err: JsValue| LoadError::Import {
+            url: url.clone(),
+            message: describe(&err),
+        };
+        let exports: js_sys::Object = JsFuture::from(import_module(&url).map_err(import_error)?)
+            .await
+            .map_err(import_error)?
+            .unchecked_into();
+
+        let module = WasmModule { url, exports };
+        // wasm-bindgen exports `init` as the default export
+        let init = module.export("default")?;
+        settle(
+            init.call0(&JsValue::NULL)
+                .map_err(|err| module.init_error(err))?,
+        )
+        .await
+        .map_err(|err| module.init_error(err))?;
+        Ok(module)
+    }
+
+    fn init_error(&self, err: JsValue) -> LoadError {
+        LoadError::Init {
+            url: self.url.clone(),
+            message: describe(&err),
+        }
+    }
+
+    fn export(&self, export: &'static str) -> Result<js_sys::Function, LoadError> {
+        js_sys::Reflect::get(&self.exports, &export.into())
+            .ok()
+            .and_then(|value| value.dyn_into::<js_sys::Function>().ok())
+            .ok_or_else(|| LoadError::MissingExport {
+                url: self.url.clone(),
+                export,
+            })
+    }
+
+    /// Calls the `run(canvas_id, scene)` export of the module, waiting for it
+    /// to start if it's async, and returns the handle it gives back. Without a
+    /// `scene` the module runs its default one.
+    pub async fn run(&self, canvas_id: &str, scene: Option<&str>) -> Result<DemoHandle, LoadError> {
+        let run_error = |err: JsValue| LoadError::Run {
+            url: self.url.clone(),
+            message: describe(&err),
+        };
+        let run = self.export("run")?;
+        let handle = settle(
+            run.call2(&JsValue::NULL, &canvas_id.into(), &scene.into())
+                .map_err(run_error)?,
+        )
+        .await
+        .map_err(run_error)?;
+        Ok(DemoHandle {
+            url: self.url.clone(),
+            handle,
+        })
+    }
+}
+
+/// A problem in a shader given to [`DemoHandle::set_shader`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShaderDiagnostic {
+    /// 1-based, or `None` when the problem isn't at a line of the shader.
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+    pub message: String,
+}
+
+impl ShaderDiagnostic {
+    fn from_js(value: &JsValue) -> Self {
+        let number = |key: &str| {
+            js_sys::Reflect::get(value, &key.into())
+                .ok()
+                .and_then(|number| number.as_f64())
+                .map(|number| number as u32)
+        };
+        Self {
+            line: number("line"),
+            column: number("column"),
+            message: js_sys::Reflect::get(value, &"message".into())
+                .ok()
+                .and_then(|message| message.as_string())
+                .unwrap_or_else(|| describe(value)),
+        }
+    }
+}
+
+/// How fast a demo renders, from [`DemoHandle::stats`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FrameStats {
+    /// Milliseconds between frames.
+    pub frame_time: f64,
+    pub fps: f64,
+    /// Milliseconds the GPU spent on a frame, if the browser can measure it.
+    pub gpu_time: Option<f64>,
+}
+
+/// The object the `run` export of a demo returns to control it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DemoHandle {
+    url: String,
+    handle: JsValue,
+}
+
+impl DemoHandle {
+    fn call(&self, method: &'static str, args: &js_sys::Array) -> Result<JsValue, LoadError> {
+        let control_error = |message| LoadError::Control {
+            url: self.url.clone(),
+            method,
+            message,
+        };
+        let function = js_sys::Reflect::get(&self.handle, &method.into())
+            .ok()
+            .and_then(|value| value.dyn_into::<js_sys::Function>().ok())
+            .ok_or_else(|| control_error("the handle has no such method".to_string()))?;
+        function
+            .apply(&self.handle, args)
+            .map_err(|err| control_error(describe(&err)))
+    }
+
+    /// The graphics API the demo picked, if it reports one.
+    pub fn backend(&self) -> Option<String> {
+        js_sys::Reflect::get(&self.handle, &"backend".into())
+            .ok()
+            .and_then(|backend| backend.as_string())
+    }
+
+    /// How fast the demo renders, if it measures it.
+    pub fn stats(&self) -> Option<FrameStats> {
+        let stats = js_sys::Reflect::get(&self.handle, &"stats".into()).ok()?;
+        if !stats.is_object() {
+            return None;
+        }
+        let number = |key: &str| {
+            js_sys::Reflect::get(&stats, &key.into())
+                .ok()
+                .and_then(|number| number.as_f64())
+        };
+        let frame_stats = FrameStats {
+            frame_time: number("frame_time").unwrap_or_default(),
+            fps: number("fps").unwrap_or_default(),
+            gpu_time: number("gpu_time"),
+        };
+        // Each read gives a new wasm-bindgen object, which the module only
+        // releases when told to
+        if let Ok(free) = js_sys::Reflect::get(&stats, &"free".into()) {
+            if let Some(free) = free.dyn_ref::<js_sys::Function>() {
+                let _ = free.call0(&stats);
+            }
+        }
+        Some(frame_stats)
+    }
+
+    #[allow(dead_code)]
+    pub fn pause(&self) -> Result<(), LoadError> {
+        self.call("pause", &js_sys::Array::new())?;
+        Ok(())
+    }
+
+    #[allow(dead_code)]
+    pub fn resume(&self) -> Result<(), LoadError> {
+        self.call("resume", &js_sys::Array::new())?;
+        Ok(())
+    }
+
+    pub fn stop(&self) -> Result<(), LoadError> {
+        self.call("stop", &js_sys::Array::new())?;
+        Ok(())
+    }
+
+    #[allow(dead_code)]
+    pub fn resize(&self, width: u32, height: u32) -> Result<(), LoadError> {
+        self.call("resize", &js_sys::Array::of2(&width.into(), &height.into()))?;
+        Ok(())
+    }
+
+    /// Caps the longest side of the canvas to `max` device pixels, or to the
+    /// default of the module when `None`.
+    pub fn set_max_resolution(&self, max: Option<u32>) -> Result<(), LoadError> {
+        let max = max.map_or(JsValue::UNDEFINED, JsValue::from);
+        self.call("set_max_resolution", &js_sys::Array::of1(&max))?;
+        Ok(())
+    }
+
+    /// Replaces the shader of the running scene, returning its problems. The
+    /// previous shader keeps running if there are any.
+    pub async fn set_shader(&self, source: &str) -> Result<Vec<ShaderDiagnostic>, LoadError> {
+        const METHOD: &str = "set_shader";
+        let diagnostics = settle(self.call(METHOD, &js_sys::Array::of1(&source.into()))?)
+            .await
+            .map_err(|err| LoadError::Control {
+                url: self.url.clone(),
+                method: METHOD,
+                message: describe(&err),
+            })?;
+        Ok(js_sys::Array::from(&diagnostics)
+            .iter()
+            .map(|diagnostic| ShaderDiagnostic::from_js(&diagnostic))
+            .collect())
+    }
+}
@@ -0,0 +1,6 @@
+//! Runs the unit tests of the build script and its modules, which cargo
+//! doesn't build as tests on its own.
+
+#[allow(dead_code)]
+#[path = "../build.rs"]
+mod build;